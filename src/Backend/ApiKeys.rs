@@ -0,0 +1,227 @@
+// Sentinel/src/Backend/ApiKeys.rs
+
+//! Sentinel API Keys
+//!
+//! Provides:
+//! - API keys for service accounts (machine-to-machine access)
+//! - Hashed secrets, scopes, expiry and last-used tracking
+//! - Rotation + revocation
+//! - A pluggable `ApiKeyStore` trait with an in-memory implementation
+//!
+//! Keys have the form `sk_<id>_<secret>`. Only `sk_<id>` (the prefix)
+//! and a SHA-256 hash of the secret are stored.
+
+use crate::Backend::Cryptography::{constant_time_eq, hash_sha256, random};
+use crate::Backend::Roles::permission_matches;
+use crate::Backend::Sessions::unix_now;
+
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const KEY_PREFIX: &str = "sk";
+
+lazy_static::lazy_static! {
+    /// Active API key store (in-memory unless replaced).
+    static ref API_KEYS: RwLock<Arc<dyn ApiKeyStore>> =
+        RwLock::new(Arc::new(MemoryApiKeyStore::default()));
+}
+
+/// A stored API key (never contains the secret itself).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Public key id.
+    pub id: String,
+    /// Service account the key belongs to.
+    pub username: String,
+    /// Displayable prefix (`sk_<id>`).
+    pub prefix: String,
+    /// SHA-256 hash of the secret part.
+    pub secret_hash: String,
+    /// Granted scopes (`*` and `prefix.*` wildcards allowed).
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+    /// Expiry (UNIX seconds), if any.
+    pub expires_at: Option<u64>,
+    /// Last successful use (UNIX seconds).
+    pub last_used: Option<u64>,
+}
+
+impl ApiKey {
+    /// Check if the key has expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.map(|exp| exp <= unix_now()).unwrap_or(false)
+    }
+
+    /// Check if the key's scopes cover a required scope.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| permission_matches(granted, scope))
+    }
+}
+
+/// A freshly issued key. `key` is shown once and never stored.
+#[derive(Clone, Debug)]
+pub struct IssuedApiKey {
+    pub key: String,
+    pub record: ApiKey,
+}
+
+/// Storage backend for API keys.
+pub trait ApiKeyStore: Send + Sync {
+    fn insert(&self, key: ApiKey) -> Result<(), String>;
+    fn get(&self, id: &str) -> Option<ApiKey>;
+    fn update(&self, key: &ApiKey) -> Result<(), String>;
+    /// Remove a key. Returns `false` if it did not exist.
+    fn remove(&self, id: &str) -> bool;
+    fn keys_for(&self, username: &str) -> Vec<ApiKey>;
+}
+
+/// Thread-safe in-memory API key store.
+#[derive(Default)]
+pub struct MemoryApiKeyStore {
+    keys: RwLock<HashMap<String, ApiKey>>,
+}
+
+impl ApiKeyStore for MemoryApiKeyStore {
+    fn insert(&self, key: ApiKey) -> Result<(), String> {
+        let mut keys = self.keys.write().map_err(|e| e.to_string())?;
+        keys.insert(key.id.clone(), key);
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Option<ApiKey> {
+        self.keys.read().ok()?.get(id).cloned()
+    }
+
+    fn update(&self, key: &ApiKey) -> Result<(), String> {
+        let mut keys = self.keys.write().map_err(|e| e.to_string())?;
+        match keys.get_mut(&key.id) {
+            Some(existing) => {
+                *existing = key.clone();
+                Ok(())
+            }
+            None => Err(format!("API key '{}' not found", key.prefix)),
+        }
+    }
+
+    fn remove(&self, id: &str) -> bool {
+        self.keys
+            .write()
+            .map(|mut keys| keys.remove(id).is_some())
+            .unwrap_or(false)
+    }
+
+    fn keys_for(&self, username: &str) -> Vec<ApiKey> {
+        self.keys
+            .read()
+            .map(|keys| keys.values().filter(|k| k.username == username).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Replace the active API key store.
+pub fn set_api_key_store(store: Arc<dyn ApiKeyStore>) {
+    if let Ok(mut active) = API_KEYS.write() {
+        *active = store;
+    }
+}
+
+/// Get a handle to the active API key store.
+pub fn api_key_store() -> Arc<dyn ApiKeyStore> {
+    match API_KEYS.read() {
+        Ok(store) => store.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Split `sk_<id>_<secret>` into `(id, secret)`.
+fn parse_key(key: &str) -> Result<(&str, &str), String> {
+    let rest = key
+        .trim()
+        .strip_prefix(KEY_PREFIX)
+        .and_then(|r| r.strip_prefix('_'))
+        .ok_or_else(|| "Malformed API key".to_string())?;
+
+    rest.split_once('_')
+        .filter(|(id, secret)| !id.is_empty() && !secret.is_empty())
+        .ok_or_else(|| "Malformed API key".to_string())
+}
+
+/// Issue a new API key for a user.
+///
+/// Callers should make sure the user is a service account
+/// (see `Auth::issue_api_key`).
+pub fn issue_api_key(
+    username: &str,
+    scopes: &[&str],
+    ttl: Option<Duration>,
+) -> Result<IssuedApiKey, String> {
+    let id = random::token(random::Alphabet::Alphanumeric, 12);
+    let secret = random::token(random::Alphabet::Alphanumeric, 32);
+    let now = unix_now();
+    let prefix = format!("{}_{}", KEY_PREFIX, id);
+
+    let record = ApiKey {
+        id,
+        username: username.into(),
+        prefix: prefix.clone(),
+        secret_hash: hash_sha256(&secret),
+        scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        created_at: now,
+        expires_at: ttl.map(|t| now + t.as_secs()),
+        last_used: None,
+    };
+
+    api_key_store().insert(record.clone())?;
+
+    Ok(IssuedApiKey {
+        key: format!("{}_{}", prefix, secret),
+        record,
+    })
+}
+
+/// Verify an API key and record its use.
+pub fn verify_api_key(key: &str) -> Result<ApiKey, String> {
+    let (id, secret) = parse_key(key)?;
+    let store = api_key_store();
+
+    let mut record = store.get(id).ok_or_else(|| "Invalid API key".to_string())?;
+    if !constant_time_eq(record.secret_hash.as_bytes(), hash_sha256(secret).as_bytes()) {
+        return Err("Invalid API key".into());
+    }
+    if record.is_expired() {
+        return Err("API key expired".into());
+    }
+
+    record.last_used = Some(unix_now());
+    store.update(&record)?;
+    Ok(record)
+}
+
+/// Replace a key with a new one that has the same owner, scopes and lifetime.
+///
+/// The old key stops working immediately.
+pub fn rotate_api_key(id: &str) -> Result<IssuedApiKey, String> {
+    let store = api_key_store();
+    let old = store.get(id).ok_or_else(|| "API key not found".to_string())?;
+
+    let ttl = old
+        .expires_at
+        .map(|exp| Duration::from_secs(exp.saturating_sub(old.created_at)));
+    let scopes: Vec<&str> = old.scopes.iter().map(String::as_str).collect();
+
+    let issued = issue_api_key(&old.username, &scopes, ttl)?;
+    store.remove(id);
+    Ok(issued)
+}
+
+/// Revoke a key. Returns `false` if it did not exist.
+pub fn revoke_api_key(id: &str) -> bool {
+    api_key_store().remove(id)
+}
+
+/// List a user's keys.
+pub fn list_api_keys(username: &str) -> Vec<ApiKey> {
+    api_key_store().keys_for(username)
+}
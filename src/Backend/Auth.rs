@@ -3,122 +3,1069 @@
 //! Sentinel Authentication Module
 //!
 //! Provides:
-//! - User registration
-//! - Login verification
-//! - Session management
-//! - Role checking
+//! - User registration (unique usernames, password policy)
+//! - Password change + forgot-password reset
+//! - Account verification
+//! - Login verification (with throttling + lockout)
+//! - TOTP two-factor login + recovery codes
+//! - Session management (login, logout, revocation)
+//! - Refresh tokens with rotation + reuse detection
+//! - Opaque or stateless (signed / encrypted) session tokens
+//! - Service accounts + API keys
+//! - Role checking (multi-role users, hierarchical roles)
+//! - Permission checking
 //! - Integration with DataHandler + Cryptography
 
 use crate::Backend::Cryptography::*;
 use crate::Backend::DataHandler::{CRUD, JSONHandler};
+use crate::Backend::Sessions::{unix_now, Session, SessionStore, MemorySessionStore, RefreshToken};
+use crate::Backend::Users::{UserRepository, MemoryUserRepository};
+use crate::Backend::PasswordPolicy::{PasswordPolicy, PolicyViolation};
+use crate::Backend::TwoFactor::{self, TotpEnrollment};
+use crate::Backend::Tokens::{self, TokenProtection};
+use crate::Backend::ApiKeys::{self, ApiKey, IssuedApiKey};
+use crate::Backend::Notifier::{Notifier, Notification, UnconfiguredNotifier};
+use crate::Backend::Roles::role_hierarchy;
+use crate::Middlend::Security::LoginThrottle;
 
-use rand::{thread_rng, Rng};
-use rand::distributions::Alphanumeric;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// Error from operations that set a password.
+#[derive(Clone, Debug)]
+pub enum AuthError {
+    /// The password breaks the active policy (one entry per failed rule).
+    Policy(Vec<PolicyViolation>),
+    /// Any other failure (unknown user, wrong password, storage, ...).
+    Other(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Policy(violations) => f.write_str(&PolicyViolation::join(violations)),
+            AuthError::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl From<String> for AuthError {
+    fn from(msg: String) -> Self {
+        AuthError::Other(msg)
+    }
+}
+
+impl From<&str> for AuthError {
+    fn from(msg: &str) -> Self {
+        AuthError::Other(msg.into())
+    }
+}
+
+impl From<AuthError> for String {
+    fn from(e: AuthError) -> Self {
+        e.to_string()
+    }
+}
 
 /// Represents a user in the system.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct User {
     pub username: String,
     pub password_hash: String,
-    pub role: String,
+    /// Roles held directly (inherited roles come from the hierarchy).
+    /// Older records with a single `role` string are still accepted.
+    #[serde(alias = "role", deserialize_with = "one_or_many")]
+    pub roles: Vec<String>,
+    /// Base32 TOTP secret (set during enrollment).
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    /// Whether TOTP is confirmed and required at login.
+    #[serde(default)]
+    pub totp_enabled: bool,
+    /// Last accepted TOTP time step (codes at or before it are rejected).
+    #[serde(default)]
+    pub totp_last_step: Option<u64>,
+    /// SHA-256 hashes of unused recovery codes.
+    #[serde(default)]
+    pub recovery_codes: Vec<String>,
+    /// Delivery address for notifications (falls back to the username).
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Whether the account has been verified.
+    #[serde(default)]
+    pub verified: bool,
+    /// Machine account: authenticates with API keys, never interactively.
+    #[serde(default)]
+    pub service_account: bool,
+}
+
+impl User {
+    /// Grant a role directly.
+    pub fn add_role(&mut self, role: impl Into<String>) {
+        let role = role.into();
+        if !self.roles.contains(&role) {
+            self.roles.push(role);
+        }
+    }
+
+    /// Remove a directly held role.
+    pub fn remove_role(&mut self, role: &str) {
+        self.roles.retain(|r| r != role);
+    }
+}
+
+/// Accept either a single role string or a list of roles.
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(role) => vec![role],
+        OneOrMany::Many(roles) => roles,
+    })
 }
 
-/// Session store (in-memory for now).
-static mut SESSIONS: Option<HashMap<String, String>> = None;
+/// How long a pending MFA challenge stays valid (seconds).
+const MFA_CHALLENGE_TTL: u64 = 5 * 60;
+
+/// Accepted TOTP clock skew (steps before/after now).
+const TOTP_SKEW: u64 = 1;
+
+/// Number of recovery codes issued on enrollment.
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Lifetime of password reset tokens (seconds).
+const RESET_TOKEN_TTL: u64 = 30 * 60;
+
+/// Lifetime of account verification tokens (seconds).
+const VERIFY_TOKEN_TTL: u64 = 24 * 60 * 60;
+
+/// Minimum time between opportunistic purges of expired sessions (seconds).
+const SESSION_PURGE_INTERVAL: u64 = 60;
+
+/// When expired sessions were last purged (UNIX seconds).
+static LAST_SESSION_PURGE: AtomicU64 = AtomicU64::new(0);
+
+const PURPOSE_RESET: &str = "password_reset";
+const PURPOSE_VERIFY: &str = "verify_account";
+
+/// How session tokens are issued.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SessionTokenMode {
+    /// Random tokens that only mean something to the session store.
+    #[default]
+    Opaque,
+    /// Self-contained tokens that any process with the token secret
+    /// can validate (see `Tokens::set_token_secret`).
+    Stateless(TokenProtection),
+}
+
+/// A login that passed the password step and awaits a second factor.
+struct MfaChallenge {
+    username: String,
+    client_id: String,
+    expires_at: u64,
+}
+
+lazy_static::lazy_static! {
+    /// Active session store (in-memory unless replaced).
+    static ref SESSIONS: RwLock<Arc<dyn SessionStore>> =
+        RwLock::new(Arc::new(MemorySessionStore::default()));
+
+    /// Active user repository (in-memory unless replaced).
+    static ref USERS: RwLock<Arc<dyn UserRepository>> =
+        RwLock::new(Arc::new(MemoryUserRepository::new()));
+
+    /// Active password policy.
+    static ref PASSWORD_POLICY: RwLock<PasswordPolicy> = RwLock::new(PasswordPolicy::default());
+
+    /// Failed-login tracker (per username and per client).
+    static ref LOGIN_THROTTLE: Mutex<LoginThrottle> = Mutex::new(LoginThrottle::default());
+
+    /// Pending MFA challenges keyed by challenge token.
+    static ref MFA_CHALLENGES: Mutex<HashMap<String, MfaChallenge>> = Mutex::new(HashMap::new());
+
+    /// How new session tokens are issued.
+    static ref SESSION_TOKEN_MODE: RwLock<SessionTokenMode> = RwLock::new(SessionTokenMode::Opaque);
+
+    /// Delivery channel for reset / verification messages (none until configured).
+    static ref NOTIFIER: RwLock<Arc<dyn Notifier>> = RwLock::new(Arc::new(UnconfiguredNotifier));
+}
+
+/// Replace the active session store.
+pub fn set_session_store(store: Arc<dyn SessionStore>) {
+    if let Ok(mut active) = SESSIONS.write() {
+        *active = store;
+    }
+}
+
+/// Get a handle to the active session store.
+pub fn session_store() -> Arc<dyn SessionStore> {
+    match SESSIONS.read() {
+        Ok(active) => active.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Choose how new session tokens are issued.
+pub fn set_session_token_mode(mode: SessionTokenMode) {
+    if let Ok(mut active) = SESSION_TOKEN_MODE.write() {
+        *active = mode;
+    }
+}
+
+/// Get the active session token mode.
+pub fn session_token_mode() -> SessionTokenMode {
+    match SESSION_TOKEN_MODE.read() {
+        Ok(mode) => *mode,
+        Err(poisoned) => *poisoned.into_inner(),
+    }
+}
+
+/// Replace the active user repository.
+pub fn set_user_repository(repo: Arc<dyn UserRepository>) {
+    if let Ok(mut active) = USERS.write() {
+        *active = repo;
+    }
+}
+
+/// Get a handle to the active user repository.
+pub fn user_repository() -> Arc<dyn UserRepository> {
+    match USERS.read() {
+        Ok(active) => active.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Look up a registered user by username.
+pub fn find_user(username: &str) -> Result<Option<User>, String> {
+    user_repository().find_by_username(username)
+}
+
+/// Replace the notifier used for reset / verification messages.
+pub fn set_notifier(notifier: Arc<dyn Notifier>) {
+    if let Ok(mut active) = NOTIFIER.write() {
+        *active = notifier;
+    }
+}
+
+fn notify(user: &User, subject: &str, body: String) -> Result<(), String> {
+    let notifier = match NOTIFIER.read() {
+        Ok(active) => active.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    };
 
-/// Initialize session store.
-fn ensure_sessions() {
-    unsafe {
-        if SESSIONS.is_none() {
-            SESSIONS = Some(HashMap::new());
+    notifier.send(&Notification {
+        to: user.email.clone().unwrap_or_else(|| user.username.clone()),
+        subject: subject.into(),
+        body,
+    })
+}
+
+/// Replace the active password policy.
+pub fn set_password_policy(policy: PasswordPolicy) {
+    if let Ok(mut active) = PASSWORD_POLICY.write() {
+        *active = policy;
+    }
+}
+
+/// Check a password against the active policy.
+///
+/// Returns structured violations the frontend can show per field.
+pub fn check_password(username: &str, password: &str) -> Result<(), Vec<PolicyViolation>> {
+    match PASSWORD_POLICY.read() {
+        Ok(policy) => policy.check(username, password),
+        Err(poisoned) => poisoned.into_inner().check(username, password),
+    }
+}
+
+/// Replace the login throttle settings (clears current lockouts).
+pub fn set_login_throttle(throttle: LoginThrottle) {
+    if let Ok(mut active) = LOGIN_THROTTLE.lock() {
+        *active = throttle;
+    }
+}
+
+fn user_lock_key(username: &str) -> String {
+    format!("user:{}", username.trim().to_lowercase())
+}
+
+fn client_lock_key(client_id: &str) -> String {
+    format!("client:{}", client_id)
+}
+
+/// Lift a username lockout (admin action).
+pub fn unlock_user(username: &str) -> bool {
+    let unlocked = LOGIN_THROTTLE
+        .lock()
+        .map(|mut t| t.unlock(&user_lock_key(username)))
+        .unwrap_or(false);
+
+    if unlocked {
+        log::info!("Login lockout lifted for user '{}'", username);
+    }
+    unlocked
+}
+
+/// Lift a client lockout (admin action).
+pub fn unlock_client(client_id: &str) -> bool {
+    let unlocked = LOGIN_THROTTLE
+        .lock()
+        .map(|mut t| t.unlock(&client_lock_key(client_id)))
+        .unwrap_or(false);
+
+    if unlocked {
+        log::info!("Login lockout lifted for client '{}'", client_id);
+    }
+    unlocked
+}
+
+/// List active lockouts (`user:<name>` / `client:<id>`) with remaining time.
+pub fn active_lockouts() -> Vec<(String, Duration)> {
+    LOGIN_THROTTLE
+        .lock()
+        .map(|t| t.active_lockouts())
+        .unwrap_or_default()
+}
+
+/// Fail if any of the given lock keys is currently locked out.
+fn ensure_not_locked(keys: &[&str]) -> Result<(), String> {
+    let mut throttle = LOGIN_THROTTLE.lock().map_err(|e| e.to_string())?;
+
+    for key in keys {
+        if let Err(remaining) = throttle.check(key) {
+            return Err(format!(
+                "Too many failed login attempts; try again in {}s",
+                remaining.as_secs().max(1)
+            ));
         }
     }
+    Ok(())
 }
 
-/// Generate a random session token.
+/// Record a failed attempt against every given lock key.
+fn record_login_failure(keys: &[&str]) {
+    if let Ok(mut throttle) = LOGIN_THROTTLE.lock() {
+        for key in keys {
+            throttle.record_failure(key);
+        }
+    }
+}
+
+/// Clear failures and strikes for a username after a successful login.
+///
+/// The client key is left alone: a success on one account must not reset
+/// the counter for attempts against other usernames from the same client.
+fn record_login_success(user_key: &str) {
+    if let Ok(mut throttle) = LOGIN_THROTTLE.lock() {
+        throttle.record_success(user_key);
+    }
+}
+
+/// Generate a random session token (48 alphanumerics, ~285 bits, OS CSPRNG).
 pub fn generate_session_token() -> String {
-    thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(48)
-        .map(char::from)
-        .collect()
+    random::token(random::Alphabet::Alphanumeric, 48)
 }
 
-/// Register a new user using chosen hashing algorithm.
+/// Register and store a new user.
+///
+/// Passwords are always hashed with Argon2id and a random salt.
+/// Fails if the username is already taken, or with `AuthError::Policy`
+/// if the password breaks the active policy.
 pub fn register_user(
     username: &str,
     password: &str,
-    role: &str,
-    salt: &str,
-    use_argon2: bool,
-) -> Result<User, String> {
-    let password_hash = if use_argon2 {
-        hash_argon2id(password, salt)?
-    } else {
-        hash_sha256(password)
+    roles: &[&str],
+) -> Result<User, AuthError> {
+    let repo = user_repository();
+    if repo.exists(username)? {
+        return Err(format!("Username '{}' already exists", username).into());
+    }
+
+    check_password(username, password).map_err(AuthError::Policy)?;
+
+    let user = User {
+        username: username.into(),
+        password_hash: hash_password(password)?,
+        roles: roles.iter().map(|r| r.to_string()).collect(),
+        ..Default::default()
     };
 
-    Ok(User {
+    repo.create(&user)?;
+    Ok(user)
+}
+
+/// Register a service account (no password; use API keys to authenticate).
+pub fn register_service_account(username: &str, roles: &[&str]) -> Result<User, String> {
+    let repo = user_repository();
+    if repo.exists(username)? {
+        return Err(format!("Username '{}' already exists", username));
+    }
+
+    let user = User {
         username: username.into(),
-        password_hash,
-        role: role.into(),
-    })
+        roles: roles.iter().map(|r| r.to_string()).collect(),
+        service_account: true,
+        verified: true,
+        ..Default::default()
+    };
+
+    repo.create(&user)?;
+    Ok(user)
 }
 
-/// Attempt login and return session token.
+/// Where a login attempt ended up.
+#[derive(Clone, Debug)]
+pub enum LoginStep {
+    /// Fully authenticated; a session and a refresh token were created.
+    Authenticated { token: String, refresh_token: String },
+    /// Password accepted, but a second factor is required.
+    /// Pass the challenge to `complete_mfa_login`.
+    MfaRequired { challenge: String },
+}
+
+/// Result of a successful login step.
+#[derive(Clone, Debug)]
+pub struct LoginOutcome {
+    pub step: LoginStep,
+    /// `true` when the user record changed in place (hash upgraded to
+    /// Argon2id or a recovery code consumed) and should be saved.
+    pub needs_save: bool,
+}
+
+impl LoginOutcome {
+    /// Session token, if the login is complete.
+    pub fn token(&self) -> Option<&str> {
+        match &self.step {
+            LoginStep::Authenticated { token, .. } => Some(token),
+            LoginStep::MfaRequired { .. } => None,
+        }
+    }
+
+    /// Refresh token, if the login is complete.
+    pub fn refresh_token(&self) -> Option<&str> {
+        match &self.step {
+            LoginStep::Authenticated { refresh_token, .. } => Some(refresh_token),
+            LoginStep::MfaRequired { .. } => None,
+        }
+    }
+}
+
+/// A fresh session token + refresh token.
+#[derive(Clone, Debug)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+/// Drop expired sessions and refresh tokens from the store.
+///
+/// Runs automatically (at most once a minute) whenever a session starts.
+pub fn purge_expired_sessions() -> usize {
+    LAST_SESSION_PURGE.store(unix_now(), Ordering::Relaxed);
+    session_store().purge_expired()
+}
+
+/// Purge if the last purge is older than `SESSION_PURGE_INTERVAL`.
+fn purge_expired_sessions_if_due() {
+    let now = unix_now();
+    let last = LAST_SESSION_PURGE.load(Ordering::Relaxed);
+    if now.saturating_sub(last) < SESSION_PURGE_INTERVAL {
+        return;
+    }
+
+    // Only the thread that wins the swap purges.
+    if LAST_SESSION_PURGE
+        .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+        .is_ok()
+    {
+        let removed = session_store().purge_expired();
+        if removed > 0 {
+            log::info!("Purged {} expired sessions and refresh tokens", removed);
+        }
+    }
+}
+
+/// Create a session for a user, linked to a refresh token family, and return its token.
+///
+/// Stateless tokens live for the store's idle timeout and are not
+/// recorded in the store.
+fn start_session(user: &User, family: &str) -> Result<String, String> {
+    purge_expired_sessions_if_due();
+    let store = session_store();
+
+    match session_token_mode() {
+        SessionTokenMode::Opaque => {
+            let token = generate_session_token();
+            let session = Session::new(token.clone(), &user.username, &store.config())
+                .with_family(family);
+            store.insert(session)?;
+            Ok(token)
+        }
+        SessionTokenMode::Stateless(protection) => Tokens::issue_session_token(
+            protection,
+            &user.username,
+            &user.roles,
+            store.config().idle_timeout.as_secs(),
+            Some(family),
+        ),
+    }
+}
+
+/// Create a session plus a refresh token in the given family (new family if `None`).
+fn start_session_pair(user: &User, family: Option<String>) -> Result<TokenPair, String> {
+    let family = family.unwrap_or_else(generate_session_token);
+    let token = start_session(user, &family)?;
+    let store = session_store();
+
+    let refresh_token = generate_session_token();
+    let now = unix_now();
+    store.insert_refresh(RefreshToken {
+        token_hash: hash_sha256(&refresh_token),
+        family,
+        username: user.username.clone(),
+        created_at: now,
+        expires_at: now + store.config().refresh_lifetime.as_secs(),
+        used: false,
+    })?;
+
+    Ok(TokenPair { token, refresh_token })
+}
+
+/// Attempt login by username, loading the user from the repository.
+///
+/// Unknown usernames fail like wrong passwords and count towards the
+/// username and `client_id` lockouts. A rehashed password is saved
+/// before returning.
+pub fn login(username: &str, password: &str, client_id: &str) -> Result<LoginOutcome, String> {
+    let Some(mut user) = find_user(username)? else {
+        let user_key = user_lock_key(username);
+        let client_key = client_lock_key(client_id);
+        let keys = [user_key.as_str(), client_key.as_str()];
+
+        ensure_not_locked(&keys)?;
+        // Spend the same hashing work as a real check.
+        let _ = hash_password(password);
+        record_login_failure(&keys);
+        return Err("Invalid username or password".into());
+    };
+
+    let outcome = login_user(&mut user, password, client_id)?;
+    if outcome.needs_save {
+        user_repository().update(&user)?;
+    }
+    Ok(outcome)
+}
+
+/// Attempt login for an already loaded user.
+///
+/// Prefer `login`, which also throttles attempts against unknown usernames.
+///
+/// The hash algorithm is detected from the stored hash. Legacy hashes
+/// are transparently upgraded to Argon2id on success.
+///
+/// Failed attempts are counted per username and per `client_id`;
+/// too many failures lock both out with exponential backoff.
+///
+/// Users with TOTP enabled get `LoginStep::MfaRequired` instead of a
+/// session token.
 pub fn login_user(
-    user: &User,
+    user: &mut User,
     password: &str,
-    salt: &str,
-    use_argon2: bool,
-) -> Option<String> {
-    let valid = if use_argon2 {
-        verify_argon2id(password, &user.password_hash)
-    } else {
-        hash_sha256(password) == user.password_hash
+    client_id: &str,
+) -> Result<LoginOutcome, String> {
+    let user_key = user_lock_key(&user.username);
+    let client_key = client_lock_key(client_id);
+    let keys = [user_key.as_str(), client_key.as_str()];
+
+    ensure_not_locked(&keys)?;
+
+    if user.service_account {
+        return Err("Service accounts must authenticate with an API key".into());
+    }
+
+    if !verify_password(password, &user.password_hash) {
+        record_login_failure(&keys);
+        return Err("Invalid username or password".into());
+    }
+
+    let mut needs_save = false;
+    if needs_rehash(&user.password_hash) {
+        if let Ok(upgraded) = hash_password(password) {
+            user.password_hash = upgraded;
+            needs_save = true;
+        }
+    }
+
+    if user.totp_enabled {
+        let challenge = generate_session_token();
+        let mut pending = MFA_CHALLENGES.lock().map_err(|e| e.to_string())?;
+        let now = unix_now();
+
+        pending.retain(|_, c| c.expires_at > now);
+        pending.insert(
+            challenge.clone(),
+            MfaChallenge {
+                username: user.username.clone(),
+                client_id: client_id.into(),
+                expires_at: now + MFA_CHALLENGE_TTL,
+            },
+        );
+
+        return Ok(LoginOutcome {
+            step: LoginStep::MfaRequired { challenge },
+            needs_save,
+        });
+    }
+
+    record_login_success(&user_key);
+    let TokenPair { token, refresh_token } = start_session_pair(user, None)?;
+
+    Ok(LoginOutcome {
+        step: LoginStep::Authenticated { token, refresh_token },
+        needs_save,
+    })
+}
+
+/// Finish a two-step login with a TOTP code or a recovery code.
+///
+/// An accepted TOTP code records its time step so it cannot be replayed,
+/// and a recovery code is consumed; either way `needs_save` is set.
+/// Wrong codes count towards the lockout like wrong passwords.
+pub fn complete_mfa_login(
+    user: &mut User,
+    challenge: &str,
+    code: &str,
+) -> Result<LoginOutcome, String> {
+    let pending = {
+        let mut pending = MFA_CHALLENGES.lock().map_err(|e| e.to_string())?;
+        match pending.get(challenge) {
+            Some(c) if c.username == user.username => pending.remove(challenge),
+            _ => None,
+        }
     };
 
-    if valid {
-        let token = generate_session_token();
-        unsafe {
-            ensure_sessions();
-            if let Some(sessions) = &mut SESSIONS {
-                sessions.insert(token.clone(), user.username.clone());
-            }
+    let pending = pending.ok_or_else(|| "Invalid MFA challenge".to_string())?;
+    if pending.expires_at <= unix_now() {
+        return Err("MFA challenge expired".into());
+    }
+
+    let user_key = user_lock_key(&user.username);
+    let client_key = client_lock_key(&pending.client_id);
+    let keys = [user_key.as_str(), client_key.as_str()];
+
+    ensure_not_locked(&keys)?;
+
+    let totp_step = user
+        .totp_secret
+        .as_deref()
+        .and_then(|secret| TwoFactor::verify_totp_after(secret, code, TOTP_SKEW, user.totp_last_step));
+    let totp_ok = totp_step.is_some();
+    if totp_step.is_some() {
+        user.totp_last_step = totp_step;
+    }
+
+    let recovery_ok = !totp_ok && TwoFactor::consume_recovery_code(&mut user.recovery_codes, code);
+
+    if !totp_ok && !recovery_ok {
+        record_login_failure(&keys);
+
+        // Keep the challenge so the user can retry until it expires.
+        if let Ok(mut challenges) = MFA_CHALLENGES.lock() {
+            challenges.insert(challenge.into(), pending);
         }
-        Some(token)
-    } else {
-        None
+        return Err("Invalid authentication code".into());
+    }
+
+    record_login_success(&user_key);
+    let TokenPair { token, refresh_token } = start_session_pair(user, None)?;
+
+    Ok(LoginOutcome {
+        step: LoginStep::Authenticated { token, refresh_token },
+        needs_save: true,
+    })
+}
+
+/// Start TOTP enrollment: generate a secret and `otpauth://` URI.
+///
+/// TOTP is not enforced until `confirm_totp_enrollment` succeeds.
+/// Save the user afterwards.
+pub fn begin_totp_enrollment(user: &mut User, issuer: &str) -> TotpEnrollment {
+    let secret = TwoFactor::generate_totp_secret();
+    let uri = TwoFactor::totp_uri(&secret, issuer, &user.username);
+
+    user.totp_secret = Some(secret.clone());
+    user.totp_enabled = false;
+
+    TotpEnrollment { secret, uri }
+}
+
+/// Confirm TOTP enrollment with a code from the authenticator app.
+///
+/// Returns freshly generated recovery codes (show them once).
+/// Save the user afterwards.
+pub fn confirm_totp_enrollment(user: &mut User, code: &str) -> Result<Vec<String>, String> {
+    let secret = user
+        .totp_secret
+        .as_deref()
+        .ok_or_else(|| "TOTP enrollment was not started".to_string())?;
+
+    let step = TwoFactor::verify_totp_after(secret, code, TOTP_SKEW, None)
+        .ok_or_else(|| "Invalid authentication code".to_string())?;
+
+    let (codes, hashes) = TwoFactor::generate_recovery_codes(RECOVERY_CODE_COUNT);
+    user.totp_enabled = true;
+    user.totp_last_step = Some(step);
+    user.recovery_codes = hashes;
+
+    Ok(codes)
+}
+
+/// Turn off TOTP for a user and drop their recovery codes.
+pub fn disable_totp(user: &mut User) {
+    user.totp_secret = None;
+    user.totp_enabled = false;
+    user.totp_last_step = None;
+    user.recovery_codes.clear();
+}
+
+/// Change a stored user's password.
+///
+/// The current password must verify and the new one must satisfy the
+/// active policy (`AuthError::Policy` otherwise). All existing sessions
+/// of the user are revoked.
+pub fn change_password(
+    username: &str,
+    current_password: &str,
+    new_password: &str,
+) -> Result<User, AuthError> {
+    let repo = user_repository();
+    let mut user = repo
+        .find_by_username(username)?
+        .ok_or_else(|| format!("User '{}' not found", username))?;
+
+    if !verify_password(current_password, &user.password_hash) {
+        return Err("Current password is incorrect".into());
+    }
+
+    check_password(&user.username, new_password).map_err(AuthError::Policy)?;
+
+    user.password_hash = hash_password(new_password)?;
+    repo.update(&user)?;
+    revoke_user_sessions(&user.username);
+
+    Ok(user)
+}
+
+/// Fingerprint of the password hash a reset token is bound to.
+///
+/// Once the password changes the fingerprint no longer matches,
+/// which makes every reset token single-use.
+fn reset_stamp(user: &User) -> String {
+    hash_sha256(&user.password_hash)[..16].to_string()
+}
+
+/// Send a password reset token through the active notifier.
+///
+/// Unknown usernames succeed silently so callers cannot probe
+/// which accounts exist.
+pub fn request_password_reset(username: &str) -> Result<(), String> {
+    let Some(user) = find_user(username)? else {
+        log::info!("Password reset requested for unknown user '{}'", username);
+        return Ok(());
+    };
+
+    let token = Tokens::issue_action_token(
+        PURPOSE_RESET,
+        &user.username,
+        &reset_stamp(&user),
+        RESET_TOKEN_TTL,
+    )?;
+
+    notify(
+        &user,
+        "Password reset",
+        format!(
+            "Use this token to reset your password (valid for {} minutes):\n\n{}",
+            RESET_TOKEN_TTL / 60,
+            token
+        ),
+    )
+}
+
+/// Set a new password using a reset token.
+///
+/// The new password must satisfy the active policy (`AuthError::Policy`
+/// otherwise). All sessions are revoked and any login lockout for the
+/// user is lifted.
+pub fn complete_password_reset(token: &str, new_password: &str) -> Result<User, AuthError> {
+    let claims = Tokens::verify_action_token(token, PURPOSE_RESET)?;
+
+    let repo = user_repository();
+    let mut user = repo
+        .find_by_username(&claims.sub)?
+        .ok_or_else(|| "Invalid reset token".to_string())?;
+
+    if !constant_time_eq(claims.stamp.as_bytes(), reset_stamp(&user).as_bytes()) {
+        return Err("Reset token has already been used".into());
+    }
+
+    check_password(&user.username, new_password).map_err(AuthError::Policy)?;
+
+    user.password_hash = hash_password(new_password)?;
+    repo.update(&user)?;
+    revoke_user_sessions(&user.username);
+    unlock_user(&user.username);
+
+    Ok(user)
+}
+
+/// Send an account verification token through the active notifier.
+///
+/// Unknown and already verified accounts succeed silently, like
+/// `request_password_reset`, so callers cannot probe which accounts exist.
+pub fn request_account_verification(username: &str) -> Result<(), String> {
+    let Some(user) = find_user(username)? else {
+        log::info!("Verification requested for unknown user '{}'", username);
+        return Ok(());
+    };
+
+    if user.verified {
+        log::info!("Verification requested for verified user '{}'", user.username);
+        return Ok(());
     }
+
+    let token = Tokens::issue_action_token(PURPOSE_VERIFY, &user.username, "", VERIFY_TOKEN_TTL)?;
+
+    notify(
+        &user,
+        "Verify your account",
+        format!("Use this token to verify your account:\n\n{}", token),
+    )
+}
+
+/// Mark an account as verified using a verification token.
+pub fn verify_account(token: &str) -> Result<User, String> {
+    let claims = Tokens::verify_action_token(token, PURPOSE_VERIFY)?;
+
+    let repo = user_repository();
+    let mut user = repo
+        .find_by_username(&claims.sub)?
+        .ok_or_else(|| "Invalid verification token".to_string())?;
+
+    if user.verified {
+        return Err("Verification token has already been used".into());
+    }
+
+    user.verified = true;
+    repo.update(&user)?;
+    Ok(user)
+}
+
+/// Validate a session token and slide its idle timer.
+///
+/// Stateless tokens are checked for signature, expiry and revocation;
+/// they have no idle timer to slide.
+pub fn touch_session(token: &str) -> Result<Session, String> {
+    if Tokens::is_stateless_token(token) {
+        let claims = Tokens::verify_session_token(token)?;
+        return Ok(Session {
+            token: token.into(),
+            username: claims.sub,
+            created_at: claims.iat,
+            last_seen: unix_now(),
+            idle_timeout: claims.exp - claims.iat,
+            max_lifetime: claims.exp - claims.iat,
+            family: claims.fam,
+        });
+    }
+
+    let store = session_store();
+
+    if store.get(token).is_none() {
+        return Err("Invalid session token".into());
+    }
+
+    store.touch(token).ok_or_else(|| "Session expired".into())
 }
 
 /// Check if a session token is valid.
 pub fn validate_session(token: &str) -> bool {
-    unsafe {
-        ensure_sessions();
-        if let Some(sessions) = &SESSIONS {
-            sessions.contains_key(token)
-        } else {
-            false
+    touch_session(token).is_ok()
+}
+
+/// End a session and the refresh token family it belongs to.
+///
+/// Returns `false` if the token was unknown. Stateless tokens are added
+/// to the revocation list.
+pub fn logout(token: &str) -> bool {
+    let family = if Tokens::is_stateless_token(token) {
+        let Ok(claims) = Tokens::verify_session_token(token) else {
+            return false;
+        };
+        Tokens::revoke_session_token(token);
+        claims.fam
+    } else {
+        match session_store().remove(token) {
+            Some(session) => session.family,
+            None => return false,
         }
+    };
+
+    if let Some(family) = family {
+        revoke_family(&family);
+    }
+    true
+}
+
+/// Revoke every refresh token and session of a family, stateless tokens included.
+fn revoke_family(family: &str) -> usize {
+    let store = session_store();
+
+    // Stateless tokens live at most one idle timeout, so the entry can expire after that.
+    let until = unix_now() + store.config().idle_timeout.as_secs();
+    Tokens::revocation_list().revoke_family(family, until);
+
+    store.revoke_family(family)
+}
+
+/// Exchange a refresh token for a new session + refresh token.
+///
+/// The presented token is used up. Presenting a token that was already
+/// used revokes its whole family, since it has most likely been stolen.
+pub fn refresh_session(refresh_token: &str) -> Result<TokenPair, String> {
+    let store = session_store();
+    let hash = hash_sha256(refresh_token.trim());
+
+    let record = store
+        .get_refresh(&hash)
+        .ok_or_else(|| "Invalid refresh token".to_string())?;
+
+    if record.is_expired_at(unix_now()) {
+        revoke_family(&record.family);
+        return Err("Refresh token expired".into());
+    }
+
+    if !store.mark_refresh_used(&hash) {
+        revoke_family(&record.family);
+        log::warn!("refresh token reuse detected for '{}'; family revoked", record.username);
+        return Err("Refresh token already used; all related tokens were revoked".into());
+    }
+
+    let user = find_user(&record.username)?
+        .ok_or_else(|| "Invalid refresh token".to_string())?;
+
+    start_session_pair(&user, Some(record.family))
+}
+
+/// Revoke a refresh token and every token and session rotated from the same login.
+///
+/// Returns `false` if the token was unknown.
+pub fn revoke_refresh_token(refresh_token: &str) -> bool {
+    match session_store().get_refresh(&hash_sha256(refresh_token.trim())) {
+        Some(record) => revoke_family(&record.family) > 0,
+        None => false,
     }
 }
 
-/// Check if a user has a required role.
+/// Revoke every session and refresh token of a user (e.g. after a password reset).
+///
+/// Stateless tokens issued before now are revoked as well.
+/// Returns the number of store sessions that were revoked.
+pub fn revoke_user_sessions(username: &str) -> usize {
+    Tokens::revocation_list().revoke_subject(username, unix_now());
+
+    let store = session_store();
+    store.remove_user_refresh(username);
+    store.remove_user(username)
+}
+
+/// List the live store sessions of a user (stateless tokens are not tracked).
+///
+/// Each session's `token` is its non-secret id, usable with `revoke_session_id`.
+pub fn list_sessions(username: &str) -> Vec<Session> {
+    session_store().sessions_for(username)
+}
+
+/// Revoke a session by id (from `list_sessions`), along with its refresh family.
+///
+/// Returns `false` if no such session exists.
+pub fn revoke_session_id(id: &str) -> bool {
+    let Some(session) = session_store().remove_by_id(id) else {
+        return false;
+    };
+
+    if let Some(family) = session.family {
+        revoke_family(&family);
+    }
+    true
+}
+
+/// Issue an API key for a service account.
+pub fn issue_api_key(
+    username: &str,
+    scopes: &[&str],
+    ttl: Option<Duration>,
+) -> Result<IssuedApiKey, String> {
+    let user = find_user(username)?.ok_or_else(|| format!("User '{}' not found", username))?;
+    if !user.service_account {
+        return Err("API keys can only be issued to service accounts".into());
+    }
+
+    ApiKeys::issue_api_key(&user.username, scopes, ttl)
+}
+
+/// Authenticate an API key and load the service account it belongs to.
+pub fn authenticate_api_key(key: &str) -> Result<(User, ApiKey), String> {
+    let record = ApiKeys::verify_api_key(key)?;
+    let user = find_user(&record.username)?
+        .filter(|u| u.service_account)
+        .ok_or_else(|| "Invalid API key".to_string())?;
+
+    Ok((user, record))
+}
+
+/// Revoke every API key of a user.
+///
+/// Returns the number of keys that were revoked.
+pub fn revoke_user_api_keys(username: &str) -> usize {
+    ApiKeys::list_api_keys(username)
+        .iter()
+        .filter(|k| ApiKeys::revoke_api_key(&k.id))
+        .count()
+}
+
+/// Check if a user has a required role, directly or through inheritance.
 pub fn user_has_role(user: &User, required: &str) -> bool {
-    user.role == required
+    role_hierarchy().satisfies(&user.roles, required)
+}
+
+/// Check if a user's roles grant a permission (e.g. `reports.read`).
+pub fn user_can(user: &User, permission: &str) -> bool {
+    role_hierarchy().grants(&user.roles, permission)
 }
 
 /// Save a user to JSON using DataHandler.
 pub fn save_user_json(user: &User) -> Result<String, String> {
     let handler = JSONHandler;
-    handler.create(user)
+    Ok(handler.create(user)?)
 }
 
 /// Load a user from JSON using DataHandler.
 pub fn load_user_json(json: &str) -> Result<User, String> {
     let handler = JSONHandler;
-    handler.read(json)
+    Ok(handler.read(json)?)
 }
@@ -3,34 +3,215 @@
 //! Sentinel Cryptography Module
 //!
 //! Provides:
-//! - Argon2id hashing (with developer-provided salt)
-//! - SHA256 hashing
+//! - Argon2id hashing (random per-user salt, configurable cost + pepper)
+//! - SHA256 hashing + HMAC-SHA256 (constant-time verification)
+//! - Ed25519 signatures (keys as PEM or hex)
+//! - OS-CSPRNG random bytes + tokens (`random`) and constant-time comparison
+//! - Self-describing password hashes with algorithm detection
 //! - AES256 encryption/decryption (developer-provided keys)
+//! - Sealed envelopes (managed nonces, key ids, associated data)
+//! - Base64 encoding helpers
+//! - Key derivation (Argon2id from passphrases, HKDF-SHA256 from key material)
+//! - Keyrings with versioned keys, rotation and lazy re-encryption
+//! - Streaming (chunked) encryption for large files over `Read`/`Write`
 
-use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use crate::Backend::DataHandler::write_atomic_private;
+
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, PasswordVerifier, Version};
 use argon2::password_hash::{SaltString, PasswordHash};
 use sha2::{Sha256, Digest};
-use aes_gcm::{Aes256Gcm, Key, Nonce}; 
-use aes_gcm::aead::{Aead, NewAead};
+use hmac::{Hmac, Mac};
+use hkdf::Hkdf;
+use ed25519_dalek::{Signer, Verifier};
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::rngs::OsRng;
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Argon2id cost parameters and optional secret pepper.
+///
+/// Tune the cost parameters to the deployment hardware. Changing them
+/// does not break existing hashes; they are upgraded on the next
+/// successful login.
+///
+/// The pepper is different: it is not recorded in the hash, so
+/// `needs_rehash` cannot detect a change and every existing hash stops
+/// verifying. Changing it requires a migration (e.g. forcing password
+/// resets).
+#[derive(Clone, Debug)]
+pub struct Argon2Config {
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Number of iterations (time cost).
+    pub iterations: u32,
+    /// Degree of parallelism (lanes).
+    pub parallelism: u32,
+    /// Secret mixed into every hash. Must stay the same to verify;
+    /// see the type docs before changing it.
+    pub pepper: Option<Vec<u8>>,
+}
+
+impl Default for Argon2Config {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+            pepper: None,
+        }
+    }
+}
+
+impl Argon2Config {
+    /// Build an Argon2id hasher from this configuration.
+    pub fn hasher(&self) -> Result<Argon2<'_>, String> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| e.to_string())?;
+
+        match &self.pepper {
+            Some(pepper) => {
+                Argon2::new_with_secret(pepper, Algorithm::Argon2id, Version::V0x13, params)
+                    .map_err(|e| e.to_string())
+            }
+            None => Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params)),
+        }
+    }
+
+    /// Check whether a parsed hash was produced with these cost parameters.
+    fn matches(&self, hash: &PasswordHash) -> bool {
+        match Params::try_from(hash) {
+            Ok(params) => {
+                params.m_cost() == self.memory_kib
+                    && params.t_cost() == self.iterations
+                    && params.p_cost() == self.parallelism
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Active Argon2 configuration.
+    static ref ARGON2_CONFIG: RwLock<Argon2Config> = RwLock::new(Argon2Config::default());
+}
+
+/// Replace the active Argon2 configuration.
+pub fn set_argon2_config(config: Argon2Config) {
+    if let Ok(mut active) = ARGON2_CONFIG.write() {
+        *active = config;
+    }
+}
+
+/// Get a copy of the active Argon2 configuration.
+pub fn argon2_config() -> Argon2Config {
+    match ARGON2_CONFIG.read() {
+        Ok(active) => active.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Hash a password using Argon2id with a freshly generated random salt.
+pub fn hash_argon2id(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    hash_argon2id_salted(password, &salt)
+}
 
 /// Hash a password using Argon2id with a developer-provided salt.
-pub fn hash_argon2id(password: &str, salt: &str) -> Result<String, String> {
-    let salt = SaltString::b64_encode(salt.as_bytes())
+///
+/// Prefer `hash_argon2id`; a shared static salt weakens every hash.
+pub fn hash_argon2id_with_salt(password: &str, salt: &str) -> Result<String, String> {
+    let salt = SaltString::encode_b64(salt.as_bytes())
         .map_err(|e| e.to_string())?;
 
-    let argon = Argon2::default();
-    let hash = argon.hash_password(password.as_bytes(), &salt)
+    hash_argon2id_salted(password, &salt)
+}
+
+fn hash_argon2id_salted(password: &str, salt: &SaltString) -> Result<String, String> {
+    let config = argon2_config();
+    let hash = config.hasher()?
+        .hash_password(password.as_bytes(), salt)
         .map_err(|e| e.to_string())?;
 
     Ok(hash.to_string())
 }
 
-/// Verify an Argon2id hash.
+/// Verify an Argon2 hash (cost parameters are read from the hash itself).
 pub fn verify_argon2id(password: &str, hash: &str) -> bool {
+    let config = argon2_config();
+
+    match (PasswordHash::new(hash), config.hasher()) {
+        (Ok(parsed), Ok(argon)) => argon.verify_password(password.as_bytes(), &parsed).is_ok(),
+        _ => false,
+    }
+}
+
+/// Algorithm a stored password hash was produced with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Argon2id,
+    Argon2i,
+    Argon2d,
+    /// Unsalted SHA-256 hex digest (legacy, should be upgraded).
+    Sha256Legacy,
+    Unknown,
+}
+
+/// Detect the algorithm of a stored hash (PHC string or SHA-256 hex).
+pub fn detect_hash_algorithm(hash: &str) -> HashAlgorithm {
     if let Ok(parsed) = PasswordHash::new(hash) {
-        Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok()
+        return match parsed.algorithm.as_str() {
+            "argon2id" => HashAlgorithm::Argon2id,
+            "argon2i" => HashAlgorithm::Argon2i,
+            "argon2d" => HashAlgorithm::Argon2d,
+            _ => HashAlgorithm::Unknown,
+        };
+    }
+
+    if hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        HashAlgorithm::Sha256Legacy
     } else {
-        false
+        HashAlgorithm::Unknown
+    }
+}
+
+/// Hash a password for storage (Argon2id, random salt, PHC string).
+pub fn hash_password(password: &str) -> Result<String, String> {
+    hash_argon2id(password)
+}
+
+/// Verify a password against a stored hash, detecting its algorithm.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    match detect_hash_algorithm(hash) {
+        HashAlgorithm::Argon2id | HashAlgorithm::Argon2i | HashAlgorithm::Argon2d => {
+            verify_argon2id(password, hash)
+        }
+        HashAlgorithm::Sha256Legacy => {
+            constant_time_eq(hash_sha256(password).as_bytes(), hash.to_lowercase().as_bytes())
+        }
+        HashAlgorithm::Unknown => false,
+    }
+}
+
+/// Check whether a stored hash should be upgraded.
+///
+/// Anything that is not Argon2id with the active cost parameters
+/// is considered weak.
+pub fn needs_rehash(hash: &str) -> bool {
+    if detect_hash_algorithm(hash) != HashAlgorithm::Argon2id {
+        return true;
+    }
+
+    match PasswordHash::new(hash) {
+        Ok(parsed) => !argon2_config().matches(&parsed),
+        Err(_) => true,
     }
 }
 
@@ -41,18 +222,918 @@ pub fn hash_sha256(input: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Compute an HMAC-SHA256 tag.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Compare two byte strings in constant time (only the length leaks).
+///
+/// Use this for every comparison involving secrets, tokens or hashes.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+/// Verify an HMAC-SHA256 tag in constant time.
+pub fn verify_hmac_sha256(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.verify_slice(tag).is_ok()
+}
+
 /// Encrypt data using AES256 (developer provides 32-byte key).
 pub fn encrypt_aes256(key: &[u8; 32], nonce: &[u8; 12], plaintext: &str) -> Result<Vec<u8>, String> {
-    let cipher = Aes256Gcm::new(Key::from_slice(key));
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
     cipher.encrypt(Nonce::from_slice(nonce), plaintext.as_bytes())
         .map_err(|e| e.to_string())
 }
 
 /// Decrypt AES256 data.
 pub fn decrypt_aes256(key: &[u8; 32], nonce: &[u8; 12], ciphertext: &[u8]) -> Result<String, String> {
-    let cipher = Aes256Gcm::new(Key::from_slice(key));
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
     let decrypted = cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
         .map_err(|e| e.to_string())?;
 
     String::from_utf8(decrypted).map_err(|e| e.to_string())
 }
+
+// -------------------------
+// SEALED ENVELOPES
+// -------------------------
+
+/// Current envelope format version.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Bytes of the key id stored in an envelope.
+pub const KEY_ID_LEN: usize = 8;
+
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = 1 + KEY_ID_LEN + NONCE_LEN;
+
+/// Short fingerprint of a key (first 8 bytes of its SHA-256).
+pub fn key_id(key: &[u8; 32]) -> [u8; KEY_ID_LEN] {
+    let digest = Sha256::digest(key);
+    let mut id = [0u8; KEY_ID_LEN];
+    id.copy_from_slice(&digest[..KEY_ID_LEN]);
+    id
+}
+
+/// Encrypt bytes into a self-contained envelope with a fresh random nonce.
+///
+/// Layout: `version (1) | key id (8) | nonce (12) | ciphertext + tag`.
+/// The header and `aad` are authenticated but not encrypted; pass the
+/// same `aad` to `open`.
+pub fn seal(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    random::fill(&mut nonce);
+
+    let mut envelope = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    envelope.push(ENVELOPE_VERSION);
+    envelope.extend_from_slice(&key_id(key));
+    envelope.extend_from_slice(&nonce);
+
+    let mut full_aad = envelope.clone();
+    full_aad.extend_from_slice(aad);
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: &full_aad })
+        .map_err(|e| e.to_string())?;
+
+    envelope.extend_from_slice(&ciphertext);
+    Ok(envelope)
+}
+
+/// Read the key id of an envelope without decrypting it.
+pub fn envelope_key_id(envelope: &[u8]) -> Result<[u8; KEY_ID_LEN], String> {
+    if envelope.len() < HEADER_LEN {
+        return Err("Envelope is truncated".into());
+    }
+    if envelope[0] != ENVELOPE_VERSION {
+        return Err(format!("Unsupported envelope version {}", envelope[0]));
+    }
+
+    let mut id = [0u8; KEY_ID_LEN];
+    id.copy_from_slice(&envelope[1..1 + KEY_ID_LEN]);
+    Ok(id)
+}
+
+/// Decrypt an envelope produced by `seal`.
+pub fn open(key: &[u8; 32], envelope: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    if envelope_key_id(envelope)? != key_id(key) {
+        return Err("Envelope was sealed with a different key".into());
+    }
+
+    let (header, ciphertext) = envelope.split_at(HEADER_LEN);
+    let nonce = &header[1 + KEY_ID_LEN..];
+
+    let mut full_aad = header.to_vec();
+    full_aad.extend_from_slice(aad);
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &full_aad })
+        .map_err(|_| "Envelope could not be decrypted (wrong key, data or aad)".to_string())
+}
+
+/// Seal bytes and return the envelope as base64.
+pub fn seal_to_base64(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<String, String> {
+    Ok(encode_base64(&seal(key, plaintext, aad)?))
+}
+
+/// Open a base64 envelope produced by `seal_to_base64`.
+pub fn open_from_base64(key: &[u8; 32], envelope: &str, aad: &[u8]) -> Result<Vec<u8>, String> {
+    open(key, &decode_base64(envelope)?, aad)
+}
+
+/// Encode bytes as standard base64.
+pub fn encode_base64(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+/// Decode standard base64 (surrounding whitespace ignored).
+pub fn decode_base64(data: &str) -> Result<Vec<u8>, String> {
+    STANDARD.decode(data.trim()).map_err(|e| e.to_string())
+}
+
+// -------------------------
+// KEY DERIVATION
+// -------------------------
+
+/// Argon2id cost parameters for passphrase-based key derivation.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Largest KDF memory cost accepted from a file (1 GiB).
+pub const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+/// Largest KDF iteration count accepted from a file.
+pub const MAX_KDF_ITERATIONS: u32 = 64;
+/// Largest KDF parallelism accepted from a file.
+pub const MAX_KDF_PARALLELISM: u32 = 16;
+
+impl KdfParams {
+    /// Check parameters loaded from a file against the maxima, so a
+    /// tampered file cannot make derivation exhaust memory or CPU.
+    pub fn bounded(self) -> Result<Self, String> {
+        if self.memory_kib > MAX_KDF_MEMORY_KIB
+            || self.iterations > MAX_KDF_ITERATIONS
+            || self.parallelism > MAX_KDF_PARALLELISM
+        {
+            return Err(format!(
+                "KDF parameters out of range (m={} KiB, t={}, p={})",
+                self.memory_kib, self.iterations, self.parallelism
+            ));
+        }
+        Ok(self)
+    }
+}
+
+/// Generate a random 256-bit key.
+pub fn generate_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    random::fill(&mut key);
+    key
+}
+
+/// Generate a random salt for key derivation.
+pub fn generate_salt() -> [u8; 16] {
+    let mut salt = [0u8; 16];
+    random::fill(&mut salt);
+    salt
+}
+
+/// Derive a 256-bit key from a passphrase with Argon2id.
+///
+/// The salt must be at least 8 bytes and stored next to the data.
+pub fn derive_key_argon2id(passphrase: &[u8], salt: &[u8], params: &KdfParams) -> Result<[u8; 32], String> {
+    let params = Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+        .map_err(|e| e.to_string())?;
+
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase, salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+/// Derive a 256-bit key from existing key material with HKDF-SHA256.
+///
+/// Use a distinct `info` per purpose (e.g. `b"sessions"`, `b"exports"`).
+pub fn derive_key_hkdf(ikm: &[u8], salt: &[u8], info: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(info, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+// -------------------------
+// KEYRING
+// -------------------------
+
+/// A versioned key held by a `Keyring`.
+#[derive(Clone)]
+pub struct KeyringEntry {
+    pub version: u32,
+    pub key: [u8; 32],
+}
+
+/// Result of `Keyring::open_and_reseal`.
+pub struct Reopened {
+    pub plaintext: Vec<u8>,
+    /// New envelope under the active key, if the data used an older key.
+    /// Store it in place of the old envelope.
+    pub resealed: Option<Vec<u8>>,
+}
+
+/// Several versioned keys, one of which is active.
+///
+/// New data is sealed with the active key; old data stays readable
+/// with older keys until it is re-sealed.
+#[derive(Clone)]
+pub struct Keyring {
+    keys: Vec<KeyringEntry>,
+    active: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyringData {
+    active: u32,
+    keys: Vec<KeyringDataEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyringDataEntry {
+    version: u32,
+    key: String,
+}
+
+/// On-disk layout of a passphrase-protected keyring.
+#[derive(Serialize, Deserialize)]
+struct KeyringFile {
+    format: u32,
+    kdf: KdfParams,
+    salt: String,
+    keyring: String,
+}
+
+const KEYRING_AAD: &[u8] = b"sentinel-keyring";
+
+impl Keyring {
+    /// Create a keyring whose active key is `key` (version 1).
+    pub fn new(key: [u8; 32]) -> Self {
+        Self {
+            keys: vec![KeyringEntry { version: 1, key }],
+            active: 1,
+        }
+    }
+
+    /// Create a keyring with a random active key.
+    pub fn generate() -> Self {
+        Self::new(generate_key())
+    }
+
+    /// Version of the active key.
+    pub fn active_version(&self) -> u32 {
+        self.active
+    }
+
+    /// Every key version held.
+    pub fn versions(&self) -> Vec<u32> {
+        self.keys.iter().map(|e| e.version).collect()
+    }
+
+    fn active_key(&self) -> &[u8; 32] {
+        &self
+            .keys
+            .iter()
+            .find(|e| e.version == self.active)
+            .expect("active key is always present")
+            .key
+    }
+
+    /// Add a key and make it active. Returns its version.
+    pub fn add_key(&mut self, key: [u8; 32]) -> u32 {
+        let version = self.keys.iter().map(|e| e.version).max().unwrap_or(0) + 1;
+        self.keys.push(KeyringEntry { version, key });
+        self.active = version;
+        version
+    }
+
+    /// Generate a new random key and make it active. Returns its version.
+    pub fn rotate(&mut self) -> u32 {
+        self.add_key(generate_key())
+    }
+
+    /// Drop an old key. The active key cannot be removed.
+    pub fn retire(&mut self, version: u32) -> Result<(), String> {
+        if version == self.active {
+            return Err("Cannot retire the active key".into());
+        }
+
+        let before = self.keys.len();
+        self.keys.retain(|e| e.version != version);
+        if self.keys.len() == before {
+            return Err(format!("Key version {} not found", version));
+        }
+        Ok(())
+    }
+
+    /// Seal data with the active key.
+    pub fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        seal(self.active_key(), plaintext, aad)
+    }
+
+    /// Open data sealed with any key in the ring.
+    pub fn open(&self, envelope: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let id = envelope_key_id(envelope)?;
+        let entry = self
+            .keys
+            .iter()
+            .find(|e| key_id(&e.key) == id)
+            .ok_or_else(|| "No key in the keyring matches this envelope".to_string())?;
+
+        open(&entry.key, envelope, aad)
+    }
+
+    /// Open data and re-seal it under the active key if it used an older key.
+    pub fn open_and_reseal(&self, envelope: &[u8], aad: &[u8]) -> Result<Reopened, String> {
+        let plaintext = self.open(envelope, aad)?;
+
+        let resealed = if envelope_key_id(envelope)? != key_id(self.active_key()) {
+            Some(self.seal(&plaintext, aad)?)
+        } else {
+            None
+        };
+
+        Ok(Reopened { plaintext, resealed })
+    }
+
+    /// Serialize the keyring encrypted under a passphrase.
+    pub fn to_protected(&self, passphrase: &str) -> Result<String, String> {
+        let data = KeyringData {
+            active: self.active,
+            keys: self
+                .keys
+                .iter()
+                .map(|e| KeyringDataEntry { version: e.version, key: encode_base64(&e.key) })
+                .collect(),
+        };
+        let json = serde_json::to_vec(&data).map_err(|e| e.to_string())?;
+
+        let kdf = KdfParams::default();
+        let salt = generate_salt();
+        let wrapping_key = derive_key_argon2id(passphrase.as_bytes(), &salt, &kdf)?;
+
+        let file = KeyringFile {
+            format: 1,
+            kdf,
+            salt: encode_base64(&salt),
+            keyring: seal_to_base64(&wrapping_key, &json, KEYRING_AAD)?,
+        };
+        serde_json::to_string_pretty(&file).map_err(|e| e.to_string())
+    }
+
+    /// Decrypt a keyring produced by `to_protected`.
+    pub fn from_protected(content: &str, passphrase: &str) -> Result<Self, String> {
+        let file: KeyringFile = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        if file.format != 1 {
+            return Err(format!("Unsupported keyring format {}", file.format));
+        }
+
+        let kdf = file.kdf.bounded()?;
+        let salt = decode_base64(&file.salt)?;
+        let wrapping_key = derive_key_argon2id(passphrase.as_bytes(), &salt, &kdf)?;
+        let json = open_from_base64(&wrapping_key, &file.keyring, KEYRING_AAD)
+            .map_err(|_| "Wrong passphrase or corrupted keyring".to_string())?;
+
+        let data: KeyringData = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+        let mut keys = Vec::new();
+        for entry in data.keys {
+            let key: [u8; 32] = decode_base64(&entry.key)?
+                .try_into()
+                .map_err(|_| format!("Key version {} is not 32 bytes", entry.version))?;
+            keys.push(KeyringEntry { version: entry.version, key });
+        }
+
+        if !keys.iter().any(|e| e.version == data.active) {
+            return Err("Keyring has no active key".into());
+        }
+        Ok(Self { keys, active: data.active })
+    }
+
+    /// Write the keyring to a passphrase-protected file.
+    ///
+    /// The file is replaced atomically and readable by its owner only.
+    pub fn save(&self, path: impl AsRef<Path>, passphrase: &str) -> Result<(), String> {
+        let content = self.to_protected(passphrase)?;
+        Ok(write_atomic_private(path.as_ref(), content.as_bytes())?)
+    }
+
+    /// Read a keyring from a passphrase-protected file.
+    pub fn load(path: impl AsRef<Path>, passphrase: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_protected(&content, passphrase)
+    }
+}
+
+// -------------------------
+// STREAMING ENCRYPTION
+// -------------------------
+
+/// Plaintext bytes per chunk in an encrypted stream.
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+const STREAM_MAGIC: &[u8; 4] = b"SNTS";
+const STREAM_NONCE_PREFIX_LEN: usize = 7;
+const STREAM_HEADER_LEN: usize = 4 + 1 + KEY_ID_LEN + 4 + STREAM_NONCE_PREFIX_LEN;
+const TAG_LEN: usize = 16;
+
+/// Build the nonce for a chunk: `prefix (7) | counter (4) | last flag (1)`.
+fn stream_nonce(prefix: &[u8], counter: u32, last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..STREAM_NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[STREAM_NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    nonce
+}
+
+/// Read until `buf` is full or the reader is exhausted.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(filled)
+}
+
+/// Encrypt a stream in authenticated chunks (STREAM construction).
+///
+/// Each chunk has its own nonce (random prefix + counter + last-chunk
+/// flag), so reordering, truncation and appending are all detected.
+/// Returns the number of plaintext bytes written.
+pub fn encrypt_stream<R: Read, W: Write>(
+    key: &[u8; 32],
+    mut reader: R,
+    mut writer: W,
+) -> Result<u64, String> {
+    let mut prefix = [0u8; STREAM_NONCE_PREFIX_LEN];
+    random::fill(&mut prefix);
+
+    let mut header = Vec::with_capacity(STREAM_HEADER_LEN);
+    header.extend_from_slice(STREAM_MAGIC);
+    header.push(ENVELOPE_VERSION);
+    header.extend_from_slice(&key_id(key));
+    header.extend_from_slice(&(STREAM_CHUNK_SIZE as u32).to_be_bytes());
+    header.extend_from_slice(&prefix);
+    writer.write_all(&header).map_err(|e| e.to_string())?;
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut counter: u32 = 0;
+    let mut total: u64 = 0;
+
+    loop {
+        let n = read_full(&mut reader, &mut buf)?;
+        // A short (possibly empty) chunk is always the last one.
+        let last = n < STREAM_CHUNK_SIZE;
+
+        let nonce = stream_nonce(&prefix, counter, last);
+        let chunk = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &buf[..n], aad: &header })
+            .map_err(|e| e.to_string())?;
+        writer.write_all(&chunk).map_err(|e| e.to_string())?;
+        total += n as u64;
+
+        if last {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| "Stream is too long".to_string())?;
+    }
+
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(total)
+}
+
+/// Decrypt a stream produced by `encrypt_stream`.
+///
+/// Chunks are written as soon as they authenticate. If an error is
+/// returned, discard everything written so far.
+/// Returns the number of plaintext bytes written.
+pub fn decrypt_stream<R: Read, W: Write>(
+    key: &[u8; 32],
+    mut reader: R,
+    mut writer: W,
+) -> Result<u64, String> {
+    let mut header = [0u8; STREAM_HEADER_LEN];
+    if read_full(&mut reader, &mut header)? < STREAM_HEADER_LEN {
+        return Err("Encrypted stream is truncated".into());
+    }
+    if &header[..4] != STREAM_MAGIC {
+        return Err("Not an encrypted stream".into());
+    }
+    if header[4] != ENVELOPE_VERSION {
+        return Err(format!("Unsupported stream version {}", header[4]));
+    }
+    if header[5..5 + KEY_ID_LEN] != key_id(key) {
+        return Err("Stream was encrypted with a different key".into());
+    }
+
+    let size_at = 5 + KEY_ID_LEN;
+    let mut size_bytes = [0u8; 4];
+    size_bytes.copy_from_slice(&header[size_at..size_at + 4]);
+    let chunk_size = u32::from_be_bytes(size_bytes) as usize;
+    if chunk_size == 0 || chunk_size > 16 * 1024 * 1024 {
+        return Err("Invalid stream chunk size".into());
+    }
+    let prefix = &header[size_at + 4..];
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let mut buf = vec![0u8; chunk_size + TAG_LEN];
+    let mut counter: u32 = 0;
+    let mut total: u64 = 0;
+
+    loop {
+        let n = read_full(&mut reader, &mut buf)?;
+        if n < TAG_LEN {
+            return Err("Encrypted stream is truncated".into());
+        }
+        let last = n < buf.len();
+
+        let nonce = stream_nonce(prefix, counter, last);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &buf[..n], aad: &header })
+            .map_err(|_| "Encrypted stream is corrupted or truncated".to_string())?;
+        writer.write_all(&plaintext).map_err(|e| e.to_string())?;
+        total += plaintext.len() as u64;
+
+        if last {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| "Stream is too long".to_string())?;
+    }
+
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(total)
+}
+
+// -------------------------
+// ED25519 SIGNATURES
+// -------------------------
+
+pub use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+
+/// Generate a new Ed25519 signing key.
+pub fn generate_signing_key() -> SigningKey {
+    SigningKey::generate(&mut OsRng)
+}
+
+/// Sign data, returning the 64-byte signature.
+pub fn sign_ed25519(key: &SigningKey, data: &[u8]) -> Vec<u8> {
+    key.sign(data).to_bytes().to_vec()
+}
+
+/// Verify an Ed25519 signature.
+pub fn verify_ed25519(key: &VerifyingKey, data: &[u8], signature: &[u8]) -> bool {
+    match Signature::from_slice(signature) {
+        Ok(signature) => key.verify(data, &signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Encode a signing key as PKCS#8 PEM.
+pub fn signing_key_to_pem(key: &SigningKey) -> Result<String, String> {
+    key.to_pkcs8_pem(LineEnding::LF)
+        .map(|pem| pem.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Decode a PKCS#8 PEM signing key.
+pub fn signing_key_from_pem(pem: &str) -> Result<SigningKey, String> {
+    SigningKey::from_pkcs8_pem(pem).map_err(|e| e.to_string())
+}
+
+/// Encode a verifying (public) key as SPKI PEM.
+pub fn verifying_key_to_pem(key: &VerifyingKey) -> Result<String, String> {
+    key.to_public_key_pem(LineEnding::LF).map_err(|e| e.to_string())
+}
+
+/// Decode an SPKI PEM verifying key.
+pub fn verifying_key_from_pem(pem: &str) -> Result<VerifyingKey, String> {
+    VerifyingKey::from_public_key_pem(pem).map_err(|e| e.to_string())
+}
+
+/// Encode a signing key's 32-byte seed as hex.
+pub fn signing_key_to_hex(key: &SigningKey) -> String {
+    encode_hex(&key.to_bytes())
+}
+
+/// Decode a signing key from a 32-byte hex seed.
+pub fn signing_key_from_hex(hex: &str) -> Result<SigningKey, String> {
+    let bytes: [u8; 32] = decode_hex(hex)?
+        .try_into()
+        .map_err(|_| "Signing key must be 32 bytes".to_string())?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// Encode a verifying key as hex.
+pub fn verifying_key_to_hex(key: &VerifyingKey) -> String {
+    encode_hex(key.as_bytes())
+}
+
+/// Decode a verifying key from hex.
+pub fn verifying_key_from_hex(hex: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = decode_hex(hex)?
+        .try_into()
+        .map_err(|_| "Verifying key must be 32 bytes".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| e.to_string())
+}
+
+/// Encode bytes as lowercase hex.
+pub fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode hex (case-insensitive, surrounding whitespace ignored).
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.trim();
+    if !hex.len().is_multiple_of(2) {
+        return Err("Hex input has an odd length".into());
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| "Invalid hex input".to_string())
+        })
+        .collect()
+}
+
+// -------------------------
+// RANDOM
+// -------------------------
+
+/// Secure random values from the operating system CSPRNG.
+pub mod random {
+    use rand::rngs::OsRng;
+    use rand::{Rng, RngCore};
+
+    /// Character set for random tokens.
+    #[derive(Clone, Copy, Debug)]
+    pub enum Alphabet {
+        /// `A-Z a-z 0-9`
+        Alphanumeric,
+        /// `0-9 a-f`
+        Hex,
+        /// RFC 4648 base32 `A-Z 2-7`
+        Base32,
+        /// URL-safe base64 `A-Z a-z 0-9 - _`
+        Base64Url,
+        /// `0-9`
+        Numeric,
+        /// Any other set of ASCII characters (at least 2).
+        Custom(&'static [u8]),
+    }
+
+    impl Alphabet {
+        fn chars(&self) -> &'static [u8] {
+            match self {
+                Alphabet::Alphanumeric => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                Alphabet::Hex => b"0123456789abcdef",
+                Alphabet::Base32 => b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+                Alphabet::Base64Url => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+                Alphabet::Numeric => b"0123456789",
+                Alphabet::Custom(chars) => chars,
+            }
+        }
+
+        /// Bits of entropy per character.
+        pub fn bits_per_char(&self) -> f64 {
+            (self.chars().len() as f64).log2()
+        }
+    }
+
+    /// Fill a buffer with random bytes.
+    pub fn fill(buf: &mut [u8]) {
+        OsRng.fill_bytes(buf);
+    }
+
+    /// Generate `len` random bytes.
+    pub fn bytes(len: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; len];
+        fill(&mut buf);
+        buf
+    }
+
+    /// Generate a random token of `len` characters (uniform, no modulo bias).
+    ///
+    /// Panics if the alphabet has fewer than 2 characters.
+    pub fn token(alphabet: Alphabet, len: usize) -> String {
+        let chars = alphabet.chars();
+        assert!(chars.len() >= 2, "token alphabet needs at least 2 characters");
+
+        (0..len)
+            .map(|_| chars[OsRng.gen_range(0..chars.len())] as char)
+            .collect()
+    }
+
+    /// Generate a token carrying at least `bits` bits of entropy.
+    ///
+    /// Panics if the alphabet has fewer than 2 characters (no entropy per character).
+    pub fn token_with_entropy(alphabet: Alphabet, bits: u32) -> String {
+        assert!(alphabet.chars().len() >= 2, "token alphabet needs at least 2 characters");
+        let len = (bits as f64 / alphabet.bits_per_char()).ceil() as usize;
+        token(alphabet, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7u8; 32];
+    const CHUNK: usize = STREAM_CHUNK_SIZE + TAG_LEN;
+
+    fn encrypt(plaintext: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        encrypt_stream(&KEY, plaintext, &mut out).unwrap();
+        out
+    }
+
+    fn decrypt(stream: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        decrypt_stream(&KEY, stream, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn stream_round_trips_at_chunk_boundaries() {
+        for len in [0, 1, STREAM_CHUNK_SIZE - 1, STREAM_CHUNK_SIZE, 2 * STREAM_CHUNK_SIZE, 2 * STREAM_CHUNK_SIZE + 5] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let stream = encrypt(&plaintext);
+            // Exact multiples end with an empty, tag-only last chunk.
+            let chunks = len / STREAM_CHUNK_SIZE + 1;
+            assert_eq!(stream.len(), STREAM_HEADER_LEN + len + chunks * TAG_LEN);
+            assert_eq!(decrypt(&stream).unwrap(), plaintext, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn stream_rejects_truncation() {
+        let stream = encrypt(&vec![1u8; 2 * STREAM_CHUNK_SIZE + 5]);
+
+        // Whole trailing chunk dropped: the new tail lacks the last-chunk flag.
+        assert!(decrypt(&stream[..STREAM_HEADER_LEN + 2 * CHUNK]).is_err());
+        // Cut in the middle of a chunk.
+        assert!(decrypt(&stream[..STREAM_HEADER_LEN + CHUNK + 100]).is_err());
+        // Header only, and less than a header.
+        assert!(decrypt(&stream[..STREAM_HEADER_LEN]).is_err());
+        assert!(decrypt(&stream[..STREAM_HEADER_LEN - 1]).is_err());
+
+        // Exact multiple: dropping the empty last chunk must still fail.
+        let exact = encrypt(&vec![2u8; 2 * STREAM_CHUNK_SIZE]);
+        assert!(decrypt(&exact[..exact.len() - TAG_LEN]).is_err());
+    }
+
+    #[test]
+    fn stream_rejects_reordered_and_appended_chunks() {
+        let stream = encrypt(&vec![3u8; 2 * STREAM_CHUNK_SIZE + 5]);
+        let body = &stream[STREAM_HEADER_LEN..];
+
+        let mut swapped = stream[..STREAM_HEADER_LEN].to_vec();
+        swapped.extend_from_slice(&body[CHUNK..2 * CHUNK]);
+        swapped.extend_from_slice(&body[..CHUNK]);
+        swapped.extend_from_slice(&body[2 * CHUNK..]);
+        assert!(decrypt(&swapped).is_err());
+
+        let mut appended = stream.clone();
+        appended.extend_from_slice(&body[..CHUNK]);
+        assert!(decrypt(&appended).is_err());
+    }
+
+    #[test]
+    fn stream_rejects_wrong_key_and_tampering() {
+        let stream = encrypt(b"streamed secret");
+
+        let mut out = Vec::new();
+        assert!(decrypt_stream(&[8u8; 32], &stream[..], &mut out).is_err());
+
+        let mut tampered = stream.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(decrypt(&tampered).is_err());
+    }
+
+    #[test]
+    fn ed25519_rejects_tampered_message_and_wrong_key() {
+        let key = generate_signing_key();
+        let signature = sign_ed25519(&key, b"signed message");
+
+        assert!(verify_ed25519(&key.verifying_key(), b"signed message", &signature));
+        assert!(!verify_ed25519(&key.verifying_key(), b"signed messagf", &signature));
+
+        let other = generate_signing_key();
+        assert!(!verify_ed25519(&other.verifying_key(), b"signed message", &signature));
+        assert!(!verify_ed25519(&key.verifying_key(), b"signed message", &signature[..63]));
+    }
+
+    #[test]
+    fn ed25519_keys_round_trip_through_pem_and_hex() {
+        let key = generate_signing_key();
+        let public = key.verifying_key();
+
+        let pem = signing_key_to_pem(&key).unwrap();
+        assert_eq!(signing_key_from_pem(&pem).unwrap().to_bytes(), key.to_bytes());
+        let pem = verifying_key_to_pem(&public).unwrap();
+        assert_eq!(verifying_key_from_pem(&pem).unwrap(), public);
+
+        let hex = signing_key_to_hex(&key);
+        assert_eq!(signing_key_from_hex(&hex).unwrap().to_bytes(), key.to_bytes());
+        let hex = verifying_key_to_hex(&public);
+        assert_eq!(verifying_key_from_hex(&hex).unwrap(), public);
+        assert!(verifying_key_from_hex(&hex[..62]).is_err());
+    }
+
+    #[test]
+    fn hex_round_trips_and_rejects_malformed_input() {
+        let bytes = [0x00, 0x0f, 0xa5, 0xff];
+        assert_eq!(encode_hex(&bytes), "000fa5ff");
+        assert_eq!(decode_hex("000fa5ff").unwrap(), bytes);
+        assert_eq!(decode_hex("000FA5FF").unwrap(), bytes);
+
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+    }
+
+    #[test]
+    fn envelope_round_trips_with_pinned_layout() {
+        let envelope = seal(&KEY, b"sealed secret", b"context").unwrap();
+
+        assert_eq!(envelope.len(), HEADER_LEN + b"sealed secret".len() + TAG_LEN);
+        assert_eq!(envelope[0], ENVELOPE_VERSION);
+        assert_eq!(envelope[1..1 + KEY_ID_LEN], key_id(&KEY));
+        assert_eq!(envelope_key_id(&envelope).unwrap(), key_id(&KEY));
+        assert_eq!(open(&KEY, &envelope, b"context").unwrap(), b"sealed secret");
+    }
+
+    #[test]
+    fn envelope_rejects_wrong_key_and_aad() {
+        let envelope = seal(&KEY, b"sealed secret", b"context").unwrap();
+
+        assert!(open(&[8u8; 32], &envelope, b"context").is_err());
+        assert!(open(&KEY, &envelope, b"other context").is_err());
+        assert!(open(&KEY, &envelope, b"").is_err());
+    }
+
+    #[test]
+    fn envelope_rejects_tampered_header_and_tag() {
+        let envelope = seal(&KEY, b"sealed secret", b"context").unwrap();
+
+        for at in [0, 1, HEADER_LEN - 1] {
+            let mut tampered = envelope.clone();
+            tampered[at] ^= 1;
+            assert!(open(&KEY, &tampered, b"context").is_err());
+        }
+
+        let mut tampered = envelope.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(open(&KEY, &tampered, b"context").is_err());
+
+        assert!(open(&KEY, &envelope[..HEADER_LEN - 1], b"context").is_err());
+    }
+}
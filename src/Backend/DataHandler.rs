@@ -6,20 +6,163 @@
 //! - JSON
 //! - YAML
 //! - XML
-//! - SQL (via connector abstraction)
+//! - SQL (SQLite connection pool behind the `sql` feature)
+//! - File-backed keyed stores (`Store<T>`) with atomic writes
+//! - A typed `DataError` for every operation
+
+use crate::Backend::Cryptography::random;
 
 use serde::{Serialize, Deserialize};
 use serde_json;
 use serde_yaml;
 use quick_xml::de::from_str as xml_from_str;
 use quick_xml::se::to_string as xml_to_string;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Errors returned by the data handlers, stores and SQL handler.
+#[derive(Debug)]
+pub enum DataError {
+    /// Data could not be serialized.
+    Serialization(String),
+    /// Input could not be parsed. Position is 1-based when known.
+    Deserialization {
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    /// The requested record or row does not exist.
+    NotFound(String),
+    /// The record already exists or violates a uniqueness constraint.
+    Conflict(String),
+    /// Reading or writing failed.
+    Io(io::Error),
+    /// The database rejected a query or connection.
+    Sql(String),
+    /// An argument was rejected (e.g. a malformed record id).
+    Invalid(String),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+            DataError::Deserialization {
+                message,
+                line,
+                column,
+            } => {
+                write!(f, "Deserialization error: {}", message)?;
+                match (line, column) {
+                    (Some(line), Some(column)) => {
+                        write!(f, " (at line {}, column {})", line, column)
+                    }
+                    (Some(line), None) => write!(f, " (at line {})", line),
+                    _ => Ok(()),
+                }
+            }
+            DataError::NotFound(what) => write!(f, "Not found: {}", what),
+            DataError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            DataError::Io(e) => write!(f, "I/O error: {}", e),
+            DataError::Sql(msg) => write!(f, "SQL error: {}", msg),
+            DataError::Invalid(msg) => write!(f, "Invalid {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DataError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DataError {
+    fn from(e: io::Error) -> Self {
+        DataError::Io(e)
+    }
+}
+
+/// Lets `?` pass a `DataError` through functions that return `Result<_, String>`.
+impl From<DataError> for String {
+    fn from(e: DataError) -> Self {
+        e.to_string()
+    }
+}
+
+#[cfg(feature = "sql")]
+impl From<sqlx::Error> for DataError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => DataError::NotFound("row".into()),
+            sqlx::Error::Io(e) => DataError::Io(e),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                DataError::Conflict(db.message().to_string())
+            }
+            e @ (sqlx::Error::ColumnDecode { .. } | sqlx::Error::Decode(_)) => {
+                DataError::Deserialization {
+                    message: e.to_string(),
+                    line: None,
+                    column: None,
+                }
+            }
+            e => DataError::Sql(e.to_string()),
+        }
+    }
+}
+
+/// Build a `Deserialization` error, dropping the " at line L column C"
+/// suffix serde already puts in `message` (`Display` adds the position).
+fn deserialization_error(message: String, line: Option<usize>, column: Option<usize>) -> DataError {
+    let message = match (line, column) {
+        (Some(line), Some(column)) => message
+            .strip_suffix(&format!(" at line {} column {}", line, column))
+            .map(str::to_string)
+            .unwrap_or(message),
+        _ => message,
+    };
+
+    DataError::Deserialization { message, line, column }
+}
+
+fn json_error(e: serde_json::Error) -> DataError {
+    if e.is_io() {
+        return DataError::Io(e.into());
+    }
+
+    let position = (e.line() > 0).then(|| (e.line(), e.column()));
+    deserialization_error(
+        e.to_string(),
+        position.map(|(line, _)| line),
+        position.map(|(_, column)| column),
+    )
+}
+
+fn yaml_error(e: serde_yaml::Error) -> DataError {
+    let location = e.location();
+    deserialization_error(
+        e.to_string(),
+        location.as_ref().map(|l| l.line()),
+        location.as_ref().map(|l| l.column()),
+    )
+}
 
 /// Generic CRUD trait for all data formats.
+///
+/// The handlers only encode and decode; use `Store<T>` to persist records.
 pub trait CRUD<T> {
-    fn create(&self, data: &T) -> Result<String, String>;
-    fn read(&self, source: &str) -> Result<T, String>;
-    fn update(&self, source: &str, data: &T) -> Result<String, String>;
-    fn delete(&self, source: &str) -> Result<String, String>;
+    fn create(&self, data: &T) -> Result<String, DataError>;
+    fn read(&self, source: &str) -> Result<T, DataError>;
+    #[deprecated(note = "ignores `source` and persists nothing; use `Store::update`")]
+    fn update(&self, source: &str, data: &T) -> Result<String, DataError>;
+    #[deprecated(note = "persists nothing; use `Store::delete`")]
+    fn delete(&self, source: &str) -> Result<String, DataError>;
 }
 
 /// JSON handler
@@ -29,19 +172,19 @@ impl<T> CRUD<T> for JSONHandler
 where
     T: Serialize + for<'de> Deserialize<'de>,
 {
-    fn create(&self, data: &T) -> Result<String, String> {
-        serde_json::to_string_pretty(data).map_err(|e| e.to_string())
+    fn create(&self, data: &T) -> Result<String, DataError> {
+        serde_json::to_string_pretty(data).map_err(|e| DataError::Serialization(e.to_string()))
     }
 
-    fn read(&self, source: &str) -> Result<T, String> {
-        serde_json::from_str(source).map_err(|e| e.to_string())
+    fn read(&self, source: &str) -> Result<T, DataError> {
+        serde_json::from_str(source).map_err(json_error)
     }
 
-    fn update(&self, _source: &str, data: &T) -> Result<String, String> {
+    fn update(&self, _source: &str, data: &T) -> Result<String, DataError> {
         self.create(data)
     }
 
-    fn delete(&self, _source: &str) -> Result<String, String> {
+    fn delete(&self, _source: &str) -> Result<String, DataError> {
         Ok("{}".into())
     }
 }
@@ -53,19 +196,19 @@ impl<T> CRUD<T> for YAMLHandler
 where
     T: Serialize + for<'de> Deserialize<'de>,
 {
-    fn create(&self, data: &T) -> Result<String, String> {
-        serde_yaml::to_string(data).map_err(|e| e.to_string())
+    fn create(&self, data: &T) -> Result<String, DataError> {
+        serde_yaml::to_string(data).map_err(|e| DataError::Serialization(e.to_string()))
     }
 
-    fn read(&self, source: &str) -> Result<T, String> {
-        serde_yaml::from_str(source).map_err(|e| e.to_string())
+    fn read(&self, source: &str) -> Result<T, DataError> {
+        serde_yaml::from_str(source).map_err(yaml_error)
     }
 
-    fn update(&self, _source: &str, data: &T) -> Result<String, String> {
+    fn update(&self, _source: &str, data: &T) -> Result<String, DataError> {
         self.create(data)
     }
 
-    fn delete(&self, _source: &str) -> Result<String, String> {
+    fn delete(&self, _source: &str) -> Result<String, DataError> {
         Ok("".into())
     }
 }
@@ -77,28 +220,717 @@ impl<T> CRUD<T> for XMLHandler
 where
     T: Serialize + for<'de> Deserialize<'de>,
 {
-    fn create(&self, data: &T) -> Result<String, String> {
-        xml_to_string(data).map_err(|e| e.to_string())
+    fn create(&self, data: &T) -> Result<String, DataError> {
+        xml_to_string(data).map_err(|e| DataError::Serialization(e.to_string()))
     }
 
-    fn read(&self, source: &str) -> Result<T, String> {
-        xml_from_str(source).map_err(|e| e.to_string())
+    fn read(&self, source: &str) -> Result<T, DataError> {
+        xml_from_str(source).map_err(|e| DataError::Deserialization {
+            message: e.to_string(),
+            line: None,
+            column: None,
+        })
     }
 
-    fn update(&self, _source: &str, data: &T) -> Result<String, String> {
+    fn update(&self, _source: &str, data: &T) -> Result<String, DataError> {
         self.create(data)
     }
 
-    fn delete(&self, _source: &str) -> Result<String, String> {
+    fn delete(&self, _source: &str) -> Result<String, DataError> {
         Ok("<deleted/>".into())
     }
 }
 
-/// SQL handler (placeholder for real DB integration)
-pub struct SQLHandler;
+/// Serialization format selector for file-backed stores.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Xml,
+}
+
+impl DataFormat {
+    /// Serialize data using the matching handler.
+    pub fn encode<T>(&self, data: &T) -> Result<String, DataError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        match self {
+            DataFormat::Json => JSONHandler.create(data),
+            DataFormat::Yaml => YAMLHandler.create(data),
+            DataFormat::Xml => XMLHandler.create(data),
+        }
+    }
+
+    /// Deserialize data using the matching handler.
+    pub fn decode<T>(&self, source: &str) -> Result<T, DataError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        match self {
+            DataFormat::Json => JSONHandler.read(source),
+            DataFormat::Yaml => YAMLHandler.read(source),
+            DataFormat::Xml => XMLHandler.read(source),
+        }
+    }
+
+    /// File extension used for records in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DataFormat::Json => "json",
+            DataFormat::Yaml => "yaml",
+            DataFormat::Xml => "xml",
+        }
+    }
+}
+
+/// Fill a uniquely named temp file next to `path` through `write`, then sync it.
+///
+/// The temp file is removed if anything fails.
+fn write_temp<T, E>(
+    path: &Path,
+    private: bool,
+    write: impl FnOnce(&mut fs::File) -> Result<T, E>,
+) -> Result<(PathBuf, T), E>
+where
+    E: From<DataError>,
+{
+    let name = path
+        .file_name()
+        .ok_or_else(|| DataError::Invalid(format!("path '{}'", path.display())))?
+        .to_string_lossy();
+    let suffix = random::token(random::Alphabet::Alphanumeric, 12);
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name, suffix));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+
+    let mut file = options.open(&tmp).map_err(DataError::Io)?;
+    let written = write(&mut file)
+        .and_then(|value| file.sync_all().map(|_| value).map_err(|e| DataError::Io(e).into()));
+    drop(file);
+
+    match written {
+        Ok(value) => Ok((tmp, value)),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            Err(e)
+        }
+    }
+}
+
+fn write_bytes(contents: &[u8]) -> impl FnOnce(&mut fs::File) -> Result<(), DataError> + '_ {
+    move |file| Ok(file.write_all(contents)?)
+}
+
+/// Write a file atomically: write a hidden temp file, sync it, then rename.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), DataError> {
+    let (tmp, ()) = write_temp(path, false, write_bytes(contents))?;
+    rename_into_place(tmp, path)
+}
+
+/// Like `write_atomic`, but the file is readable by its owner only (0600 on Unix).
+pub fn write_atomic_private(path: &Path, contents: &[u8]) -> Result<(), DataError> {
+    write_atomic_private_with(path, write_bytes(contents))
+}
+
+/// Stream an owner-only file into place: `write` fills a hidden temp file,
+/// which replaces `path` only if `write` succeeds. Readers never see a
+/// partial file, and a failure leaves `path` untouched.
+pub fn write_atomic_private_with<T, E>(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> Result<T, E>,
+) -> Result<T, E>
+where
+    E: From<DataError>,
+{
+    let (tmp, value) = write_temp(path, true, write)?;
+    rename_into_place(tmp, path)?;
+    Ok(value)
+}
+
+fn rename_into_place(tmp: PathBuf, path: &Path) -> Result<(), DataError> {
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        DataError::Io(e)
+    })
+}
+
+/// Like `write_atomic`, but fails with `Conflict` if `path` already exists.
+pub fn write_atomic_new(path: &Path, contents: &[u8]) -> Result<(), DataError> {
+    let (tmp, ()) = write_temp(path, false, write_bytes(contents))?;
+    link_new(tmp, path)
+}
+
+/// Like `write_atomic_new`, but the file is readable by its owner only (0600 on Unix).
+pub fn write_atomic_new_private(path: &Path, contents: &[u8]) -> Result<(), DataError> {
+    let (tmp, ()) = write_temp(path, true, write_bytes(contents))?;
+    link_new(tmp, path)
+}
+
+fn link_new(tmp: PathBuf, path: &Path) -> Result<(), DataError> {
+    // A hard link never replaces an existing file, so concurrent creators
+    // cannot overwrite each other.
+    let linked = fs::hard_link(&tmp, path);
+    let _ = fs::remove_file(&tmp);
+
+    match linked {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(DataError::Conflict(format!(
+            "'{}' already exists",
+            path.display()
+        ))),
+        Err(e) => Err(DataError::Io(e)),
+    }
+}
+
+/// How a `Store` lays out its records on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoreLayout {
+    /// One file per record: `<dir>/<id>.<ext>`.
+    Directory,
+    /// All records in a single file.
+    SingleFile,
+}
+
+/// One record in a single-file store.
+#[derive(Serialize, Deserialize)]
+struct StoreRecord<T> {
+    id: String,
+    value: T,
+}
+
+/// On-disk layout of a single-file store.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "store")]
+struct StoreFile<T> {
+    #[serde(default = "Vec::new", rename = "record")]
+    records: Vec<StoreRecord<T>>,
+}
+
+/// File-backed keyed records in JSON, YAML or XML.
+///
+/// Every write goes through `write_atomic`, so a crash never leaves
+/// a half-written record behind. Ids may contain letters, digits,
+/// `-` and `_`.
+pub struct Store<T> {
+    path: PathBuf,
+    format: DataFormat,
+    layout: StoreLayout,
+    write_lock: Mutex<()>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Store<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    /// Open a store keeping one file per record in `dir` (created if missing).
+    pub fn directory(dir: impl Into<PathBuf>, format: DataFormat) -> Result<Self, DataError> {
+        let path = dir.into();
+        fs::create_dir_all(&path)?;
+        Ok(Self::with_layout(path, format, StoreLayout::Directory))
+    }
+
+    /// Open a store keeping every record in one file (created on first write).
+    pub fn file(path: impl Into<PathBuf>, format: DataFormat) -> Result<Self, DataError> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self::with_layout(path, format, StoreLayout::SingleFile))
+    }
+
+    fn with_layout(path: PathBuf, format: DataFormat, layout: StoreLayout) -> Self {
+        Self {
+            path,
+            format,
+            layout,
+            write_lock: Mutex::new(()),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn layout(&self) -> StoreLayout {
+        self.layout
+    }
+
+    /// Add a new record. Fails if the id is already taken.
+    pub fn create(&self, id: &str, value: &T) -> Result<(), DataError> {
+        validate_id(id)?;
+        let _guard = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        match self.layout {
+            StoreLayout::Directory => {
+                let path = self.record_path(id);
+                let contents = self.format.encode(value)?;
+                match write_atomic_new(&path, contents.as_bytes()) {
+                    Err(DataError::Conflict(_)) => Err(DataError::Conflict(format!(
+                        "record '{}' already exists",
+                        id
+                    ))),
+                    result => result,
+                }
+            }
+            StoreLayout::SingleFile => {
+                let mut file = self.load_file()?;
+                if file.records.iter().any(|r| r.id == id) {
+                    return Err(DataError::Conflict(format!(
+                        "record '{}' already exists",
+                        id
+                    )));
+                }
+                file.records.push(StoreRecord {
+                    id: id.into(),
+                    value: value.clone(),
+                });
+                self.save_file(&file)
+            }
+        }
+    }
+
+    /// Read a record by id.
+    pub fn read(&self, id: &str) -> Result<T, DataError> {
+        validate_id(id)?;
+
+        match self.layout {
+            StoreLayout::Directory => {
+                let path = self.record_path(id);
+                if !path.exists() {
+                    return Err(DataError::NotFound(format!("record '{}'", id)));
+                }
+                let source = fs::read_to_string(&path)?;
+                self.format.decode(&source)
+            }
+            StoreLayout::SingleFile => self
+                .load_file()?
+                .records
+                .into_iter()
+                .find(|r| r.id == id)
+                .map(|r| r.value)
+                .ok_or_else(|| DataError::NotFound(format!("record '{}'", id))),
+        }
+    }
 
+    /// Replace an existing record.
+    pub fn update(&self, id: &str, value: &T) -> Result<(), DataError> {
+        validate_id(id)?;
+        let _guard = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        match self.layout {
+            StoreLayout::Directory => {
+                let path = self.record_path(id);
+                if !path.exists() {
+                    return Err(DataError::NotFound(format!("record '{}'", id)));
+                }
+                write_atomic(&path, self.format.encode(value)?.as_bytes())
+            }
+            StoreLayout::SingleFile => {
+                let mut file = self.load_file()?;
+                let record = file
+                    .records
+                    .iter_mut()
+                    .find(|r| r.id == id)
+                    .ok_or_else(|| DataError::NotFound(format!("record '{}'", id)))?;
+                record.value = value.clone();
+                self.save_file(&file)
+            }
+        }
+    }
+
+    /// Delete a record by id.
+    pub fn delete(&self, id: &str) -> Result<(), DataError> {
+        validate_id(id)?;
+        let _guard = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        match self.layout {
+            StoreLayout::Directory => {
+                let path = self.record_path(id);
+                if !path.exists() {
+                    return Err(DataError::NotFound(format!("record '{}'", id)));
+                }
+                fs::remove_file(&path).map_err(DataError::from)
+            }
+            StoreLayout::SingleFile => {
+                let mut file = self.load_file()?;
+                let before = file.records.len();
+                file.records.retain(|r| r.id != id);
+                if file.records.len() == before {
+                    return Err(DataError::NotFound(format!("record '{}'", id)));
+                }
+                self.save_file(&file)
+            }
+        }
+    }
+
+    /// Check whether a record exists.
+    pub fn exists(&self, id: &str) -> Result<bool, DataError> {
+        validate_id(id)?;
+
+        match self.layout {
+            StoreLayout::Directory => Ok(self.record_path(id).exists()),
+            StoreLayout::SingleFile => Ok(self.load_file()?.records.iter().any(|r| r.id == id)),
+        }
+    }
+
+    /// List every record id (sorted).
+    pub fn ids(&self) -> Result<Vec<String>, DataError> {
+        let mut ids: Vec<String> = match self.layout {
+            StoreLayout::Directory => fs::read_dir(&self.path)?
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    if path.extension()? != self.format.extension() {
+                        return None;
+                    }
+                    let id = path.file_stem()?.to_str()?.to_string();
+                    validate_id(&id).ok().map(|_| id)
+                })
+                .collect(),
+            StoreLayout::SingleFile => self
+                .load_file()?
+                .records
+                .into_iter()
+                .map(|r| r.id)
+                .collect(),
+        };
+
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// List every record with its id (sorted by id).
+    pub fn list(&self) -> Result<Vec<(String, T)>, DataError> {
+        match self.layout {
+            StoreLayout::Directory => self
+                .ids()?
+                .into_iter()
+                .map(|id| self.read(&id).map(|value| (id, value)))
+                .collect(),
+            StoreLayout::SingleFile => {
+                let mut records: Vec<(String, T)> = self
+                    .load_file()?
+                    .records
+                    .into_iter()
+                    .map(|r| (r.id, r.value))
+                    .collect();
+                records.sort_by(|a, b| a.0.cmp(&b.0));
+                Ok(records)
+            }
+        }
+    }
+
+    fn record_path(&self, id: &str) -> PathBuf {
+        self.path
+            .join(format!("{}.{}", id, self.format.extension()))
+    }
+
+    fn load_file(&self) -> Result<StoreFile<T>, DataError> {
+        if !self.path.exists() {
+            return Ok(StoreFile {
+                records: Vec::new(),
+            });
+        }
+
+        let source = fs::read_to_string(&self.path)?;
+        if source.trim().is_empty() {
+            return Ok(StoreFile {
+                records: Vec::new(),
+            });
+        }
+        self.format.decode(&source)
+    }
+
+    fn save_file(&self, file: &StoreFile<T>) -> Result<(), DataError> {
+        write_atomic(&self.path, self.format.encode(file)?.as_bytes())
+    }
+}
+
+fn validate_id(id: &str) -> Result<(), DataError> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(DataError::Invalid(format!("record id '{}'", id)))
+    }
+}
+
+/// A bound SQL parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl From<bool> for SqlValue {
+    fn from(value: bool) -> Self {
+        SqlValue::Bool(value)
+    }
+}
+
+impl From<i64> for SqlValue {
+    fn from(value: i64) -> Self {
+        SqlValue::Integer(value)
+    }
+}
+
+impl From<f64> for SqlValue {
+    fn from(value: f64) -> Self {
+        SqlValue::Real(value)
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        SqlValue::Text(value.into())
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        SqlValue::Text(value)
+    }
+}
+
+impl From<Vec<u8>> for SqlValue {
+    fn from(value: Vec<u8>) -> Self {
+        SqlValue::Blob(value)
+    }
+}
+
+impl<V: Into<SqlValue>> From<Option<V>> for SqlValue {
+    fn from(value: Option<V>) -> Self {
+        value.map(Into::into).unwrap_or(SqlValue::Null)
+    }
+}
+
+/// SQL handler backed by an SQLite connection pool (`sql` feature).
+///
+/// Queries take `?` placeholders bound from `SqlValue`s, and rows are
+/// decoded into any `Deserialize` type by column name.
+///
+/// Calls block on an internal Tokio runtime, so this handler must not
+/// be used from inside an async task.
+#[cfg(feature = "sql")]
+pub struct SQLHandler {
+    pool: sqlx::SqlitePool,
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "sql")]
+type SqliteQuery<'q> = sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>;
+
+#[cfg(feature = "sql")]
+fn bind_params<'q>(sql: &'q str, params: &'q [SqlValue]) -> SqliteQuery<'q> {
+    params
+        .iter()
+        .fold(sqlx::query(sql), |query, param| match param {
+            SqlValue::Null => query.bind(None::<i64>),
+            SqlValue::Bool(v) => query.bind(*v),
+            SqlValue::Integer(v) => query.bind(*v),
+            SqlValue::Real(v) => query.bind(*v),
+            SqlValue::Text(v) => query.bind(v.as_str()),
+            SqlValue::Blob(v) => query.bind(v.as_slice()),
+        })
+}
+
+/// Whether a URL names an in-memory SQLite database.
+#[cfg(feature = "sql")]
+fn is_memory_url(url: &str) -> bool {
+    url.contains(":memory:") || url.contains("mode=memory")
+}
+
+/// Decode a row into `T` through a JSON object keyed by column name.
+#[cfg(feature = "sql")]
+fn decode_row<T>(row: &sqlx::sqlite::SqliteRow) -> Result<T, DataError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    use sqlx::{Column, Row, TypeInfo, ValueRef};
+
+    let mut object = serde_json::Map::new();
+    for (i, column) in row.columns().iter().enumerate() {
+        let raw = row.try_get_raw(i)?;
+
+        let value = if raw.is_null() {
+            serde_json::Value::Null
+        } else if column.type_info().name() == "BOOLEAN" {
+            serde_json::Value::Bool(row.try_get::<bool, _>(i)?)
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => row.try_get::<i64, _>(i)?.into(),
+                "REAL" => row.try_get::<f64, _>(i)?.into(),
+                "BLOB" => row.try_get::<Vec<u8>, _>(i)?.into(),
+                _ => row.try_get::<String, _>(i)?.into(),
+            }
+        };
+
+        object.insert(column.name().to_string(), value);
+    }
+
+    serde_json::from_value(serde_json::Value::Object(object)).map_err(|e| {
+        DataError::Deserialization {
+            message: e.to_string(),
+            line: None,
+            column: None,
+        }
+    })
+}
+
+#[cfg(feature = "sql")]
 impl SQLHandler {
-    pub fn query(&self, sql: &str) -> Result<String, String> {
-        Ok(format!("Executed SQL: {}", sql))
+    /// Connect to a database (e.g. `sqlite://app.db?mode=rwc`).
+    ///
+    /// Every connection to `sqlite::memory:` opens its own empty database,
+    /// so in-memory URLs get a single long-lived connection. Share the
+    /// handler (`Arc<SQLHandler>`) to use that database from several
+    /// stores, and do not call the handler from inside `transaction`.
+    pub fn connect(url: &str) -> Result<Self, DataError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+
+        let options = if is_memory_url(url) {
+            sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            sqlx::sqlite::SqlitePoolOptions::new()
+        };
+        let pool = runtime.block_on(options.connect(url))?;
+
+        Ok(Self { pool, runtime })
+    }
+
+    /// Run a statement, returning the number of affected rows.
+    pub fn execute(&self, sql: &str, params: &[SqlValue]) -> Result<u64, DataError> {
+        self.runtime
+            .block_on(bind_params(sql, params).execute(&self.pool))
+            .map(|r| r.rows_affected())
+            .map_err(DataError::from)
+    }
+
+    /// Fetch exactly one row. Fails if the query returns no rows.
+    pub fn fetch_one<T>(&self, sql: &str, params: &[SqlValue]) -> Result<T, DataError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let row = self
+            .runtime
+            .block_on(bind_params(sql, params).fetch_one(&self.pool))?;
+        decode_row(&row)
+    }
+
+    /// Fetch at most one row.
+    pub fn fetch_optional<T>(&self, sql: &str, params: &[SqlValue]) -> Result<Option<T>, DataError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let row = self
+            .runtime
+            .block_on(bind_params(sql, params).fetch_optional(&self.pool))?;
+        row.as_ref().map(decode_row).transpose()
+    }
+
+    /// Fetch every row.
+    pub fn fetch_all<T>(&self, sql: &str, params: &[SqlValue]) -> Result<Vec<T>, DataError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let rows = self
+            .runtime
+            .block_on(bind_params(sql, params).fetch_all(&self.pool))?;
+        rows.iter().map(decode_row).collect()
+    }
+
+    /// Run several statements atomically.
+    ///
+    /// The transaction commits if `work` returns `Ok` and rolls back otherwise.
+    pub fn transaction<R>(
+        &self,
+        work: impl FnOnce(&mut SQLTransaction<'_>) -> Result<R, DataError>,
+    ) -> Result<R, DataError> {
+        let tx = self.runtime.block_on(self.pool.begin())?;
+
+        let mut handle = SQLTransaction {
+            tx,
+            runtime: &self.runtime,
+        };
+        match work(&mut handle) {
+            Ok(result) => {
+                self.runtime.block_on(handle.tx.commit())?;
+                Ok(result)
+            }
+            Err(e) => {
+                let _ = self.runtime.block_on(handle.tx.rollback());
+                Err(e)
+            }
+        }
+    }
+}
+
+/// An open transaction, passed to `SQLHandler::transaction`.
+#[cfg(feature = "sql")]
+pub struct SQLTransaction<'a> {
+    tx: sqlx::Transaction<'static, sqlx::Sqlite>,
+    runtime: &'a tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "sql")]
+impl SQLTransaction<'_> {
+    /// Run a statement inside the transaction.
+    pub fn execute(&mut self, sql: &str, params: &[SqlValue]) -> Result<u64, DataError> {
+        self.runtime
+            .block_on(bind_params(sql, params).execute(&mut *self.tx))
+            .map(|r| r.rows_affected())
+            .map_err(DataError::from)
+    }
+
+    /// Fetch exactly one row inside the transaction.
+    pub fn fetch_one<T>(&mut self, sql: &str, params: &[SqlValue]) -> Result<T, DataError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let row = self
+            .runtime
+            .block_on(bind_params(sql, params).fetch_one(&mut *self.tx))?;
+        decode_row(&row)
+    }
+
+    /// Fetch every row inside the transaction.
+    pub fn fetch_all<T>(&mut self, sql: &str, params: &[SqlValue]) -> Result<Vec<T>, DataError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let rows = self
+            .runtime
+            .block_on(bind_params(sql, params).fetch_all(&mut *self.tx))?;
+        rows.iter().map(decode_row).collect()
     }
 }
@@ -0,0 +1,88 @@
+// Sentinel/src/Backend/Notifier.rs
+
+//! Sentinel Notifier
+//!
+//! Provides:
+//! - A pluggable `Notifier` trait for delivering messages to users
+//!   (password reset links, verification codes, ...)
+//! - A default notifier that refuses to send until one is configured
+//! - A stdout notifier for local testing
+//! - A file notifier that appends messages to a log file
+//!
+//! Real deployments plug in their own email/SMS implementation.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// A message to deliver to a user.
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivery channel for notifications.
+pub trait Notifier: Send + Sync {
+    fn send(&self, notification: &Notification) -> Result<(), String>;
+}
+
+/// Default notifier: fails every send so tokens are never leaked to
+/// stdout or logs by accident. Configure a real notifier with
+/// `Auth::set_notifier`.
+pub struct UnconfiguredNotifier;
+
+impl Notifier for UnconfiguredNotifier {
+    fn send(&self, _notification: &Notification) -> Result<(), String> {
+        Err("no notifier configured".into())
+    }
+}
+
+/// Prints notifications to stdout (local testing only, opt-in).
+pub struct StdoutNotifier;
+
+impl Notifier for StdoutNotifier {
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        println!(
+            "To: {}\nSubject: {}\n\n{}\n",
+            notification.to, notification.subject, notification.body
+        );
+        Ok(())
+    }
+}
+
+/// Appends notifications to a file (local testing only, opt-in).
+pub struct FileNotifier {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileNotifier {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl Notifier for FileNotifier {
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+
+        writeln!(
+            file,
+            "To: {}\nSubject: {}\n\n{}\n---",
+            notification.to, notification.subject, notification.body
+        )
+        .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,241 @@
+// Sentinel/src/Backend/PasswordPolicy.rs
+
+//! Sentinel Password Policy
+//!
+//! Provides:
+//! - Minimum / maximum length rules
+//! - Required character classes
+//! - A max-repeat rule for consecutive characters
+//! - Rejection of passwords containing the username
+//! - A local breached/common-password list loaded from a file
+//!
+//! Violations are returned as structured values so the frontend
+//! can display them next to the password field.
+
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::fs;
+
+/// A single failed password rule.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    /// Form field the violation belongs to (always `"password"`).
+    pub field: String,
+    /// Stable machine-readable code, e.g. `"too_short"`.
+    pub code: String,
+    /// Human-readable message.
+    pub message: String,
+}
+
+impl PolicyViolation {
+    fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: "password".into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Join the messages of several violations into one error string.
+    pub fn join(violations: &[PolicyViolation]) -> String {
+        violations
+            .iter()
+            .map(|v| v.message.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Configurable password rules.
+#[derive(Clone, Debug)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Maximum run of the same character (e.g. 3 rejects `"aaaa"`).
+    pub max_repeat: Option<usize>,
+    pub reject_username: bool,
+    /// Lowercased breached/common passwords.
+    pub common_passwords: HashSet<String>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            max_repeat: Some(3),
+            reject_username: true,
+            common_passwords: HashSet::new(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Create a policy with the default rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the allowed length range.
+    pub fn with_length(mut self, min: usize, max: usize) -> Self {
+        self.min_length = min;
+        self.max_length = max;
+        self
+    }
+
+    /// Choose which character classes are required.
+    pub fn require_classes(
+        mut self,
+        lowercase: bool,
+        uppercase: bool,
+        digit: bool,
+        symbol: bool,
+    ) -> Self {
+        self.require_lowercase = lowercase;
+        self.require_uppercase = uppercase;
+        self.require_digit = digit;
+        self.require_symbol = symbol;
+        self
+    }
+
+    /// Set the max-repeat rule (`None` disables it).
+    pub fn with_max_repeat(mut self, max: Option<usize>) -> Self {
+        self.max_repeat = max;
+        self
+    }
+
+    /// Enable or disable rejecting passwords that contain the username.
+    pub fn reject_username(mut self, value: bool) -> Self {
+        self.reject_username = value;
+        self
+    }
+
+    /// Add passwords to the common-password list.
+    pub fn with_common_passwords<I, S>(mut self, passwords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.common_passwords.extend(
+            passwords
+                .into_iter()
+                .map(|p| p.as_ref().trim().to_lowercase())
+                .filter(|p| !p.is_empty()),
+        );
+        self
+    }
+
+    /// Load a common-password list (one per line, `#` for comments).
+    pub fn load_common_passwords(self, path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let lines = content
+            .lines()
+            .filter(|l| !l.trim_start().starts_with('#'))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        Ok(self.with_common_passwords(lines))
+    }
+
+    /// Check a password, returning every rule it breaks.
+    pub fn check(&self, username: &str, password: &str) -> Result<(), Vec<PolicyViolation>> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+
+        if length < self.min_length {
+            violations.push(PolicyViolation::new(
+                "too_short",
+                format!("Password must be at least {} characters", self.min_length),
+            ));
+        }
+        if length > self.max_length {
+            violations.push(PolicyViolation::new(
+                "too_long",
+                format!("Password must be at most {} characters", self.max_length),
+            ));
+        }
+
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            violations.push(PolicyViolation::new(
+                "missing_lowercase",
+                "Password must contain a lowercase letter",
+            ));
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            violations.push(PolicyViolation::new(
+                "missing_uppercase",
+                "Password must contain an uppercase letter",
+            ));
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(PolicyViolation::new(
+                "missing_digit",
+                "Password must contain a digit",
+            ));
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric()) {
+            violations.push(PolicyViolation::new(
+                "missing_symbol",
+                "Password must contain a symbol",
+            ));
+        }
+
+        if let Some(max) = self.max_repeat {
+            if longest_run(password) > max {
+                violations.push(PolicyViolation::new(
+                    "too_many_repeats",
+                    format!("Password must not repeat a character more than {} times in a row", max),
+                ));
+            }
+        }
+
+        let lowered = password.to_lowercase();
+        let name = username.trim().to_lowercase();
+        if self.reject_username && name.chars().count() >= 3 && lowered.contains(&name) {
+            violations.push(PolicyViolation::new(
+                "contains_username",
+                "Password must not contain the username",
+            ));
+        }
+
+        if self.common_passwords.contains(&lowered) {
+            violations.push(PolicyViolation::new(
+                "too_common",
+                "Password is too common or has appeared in a breach",
+            ));
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+/// Length of the longest run of one repeated character.
+fn longest_run(input: &str) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous = None;
+
+    for c in input.chars() {
+        if Some(c) == previous {
+            current += 1;
+        } else {
+            current = 1;
+            previous = Some(c);
+        }
+        longest = longest.max(current);
+    }
+
+    longest
+}
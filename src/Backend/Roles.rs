@@ -4,15 +4,176 @@
 //!
 //! Provides:
 //! - Role definitions
+//! - Role hierarchy with inheritance (e.g. admin ⊇ editor ⊇ viewer)
+//! - Role → permission mapping (e.g. `reports.read`, `users.*`)
 //! - Mapping roles to UI views/components
 //! - Resolving which view a user should see
 //!
 //! This integrates with the Frontend GUI system.
 
 use crate::Backend::Auth::User;
+use crate::Backend::DataHandler::{CRUD, YAMLHandler};
 use crate::Frontend::GUI::{Renderable, GUIContainer};
 
-use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::RwLock;
+
+/// A single role, the roles it inherits from and the permissions it grants.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RoleDefinition {
+    #[serde(default)]
+    pub inherits: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// Check whether a granted permission covers a required one.
+///
+/// `*` covers everything; `reports.*` covers `reports.read`.
+pub fn permission_matches(granted: &str, required: &str) -> bool {
+    if granted == "*" || granted == required {
+        return true;
+    }
+
+    match granted.strip_suffix(".*") {
+        Some(prefix) => required
+            .strip_prefix(prefix)
+            .map(|rest| rest.starts_with('.'))
+            .unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Layout of `Describe/roles.yaml`.
+///
+/// ```yaml
+/// roles:
+///   admin:
+///     inherits: [editor]
+///     permissions: [users.*]
+///   editor:
+///     inherits: [viewer]
+///     permissions: [reports.write]
+///   viewer:
+///     permissions: [reports.read]
+/// ```
+#[derive(Serialize, Deserialize)]
+struct RolesFile {
+    #[serde(default)]
+    roles: HashMap<String, Option<RoleDefinition>>,
+}
+
+/// Role hierarchy with inheritance.
+///
+/// A user holding a role also holds every role it inherits,
+/// directly or transitively. Cycles are tolerated.
+#[derive(Clone, Debug, Default)]
+pub struct RoleHierarchy {
+    pub roles: HashMap<String, RoleDefinition>,
+}
+
+impl RoleHierarchy {
+    /// Create an empty hierarchy (every role stands alone).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define a role and the roles it inherits.
+    pub fn define(mut self, role: impl Into<String>, inherits: &[&str]) -> Self {
+        let def = self.roles.entry(role.into()).or_default();
+        def.inherits = inherits.iter().map(|r| r.to_string()).collect();
+        self
+    }
+
+    /// Grant permissions to a role.
+    pub fn grant(mut self, role: impl Into<String>, permissions: &[&str]) -> Self {
+        let def = self.roles.entry(role.into()).or_default();
+        for permission in permissions {
+            if !def.permissions.iter().any(|p| p == permission) {
+                def.permissions.push(permission.to_string());
+            }
+        }
+        self
+    }
+
+    /// Parse a hierarchy from `roles.yaml` content.
+    pub fn from_yaml(yaml: &str) -> Result<Self, String> {
+        let file: RolesFile = YAMLHandler.read(yaml)?;
+
+        Ok(Self {
+            roles: file
+                .roles
+                .into_iter()
+                .map(|(name, def)| (name, def.unwrap_or_default()))
+                .collect(),
+        })
+    }
+
+    /// Expand roles to include everything they inherit.
+    ///
+    /// Directly held roles come first, then inherited ones
+    /// in breadth-first order.
+    pub fn effective_roles(&self, roles: &[String]) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut ordered = Vec::new();
+        let mut queue: VecDeque<&str> = roles.iter().map(String::as_str).collect();
+
+        while let Some(role) = queue.pop_front() {
+            if !seen.insert(role) {
+                continue;
+            }
+            ordered.push(role.to_string());
+
+            if let Some(def) = self.roles.get(role) {
+                queue.extend(def.inherits.iter().map(String::as_str));
+            }
+        }
+
+        ordered
+    }
+
+    /// Check whether the given roles satisfy a required role.
+    pub fn satisfies(&self, roles: &[String], required: &str) -> bool {
+        self.effective_roles(roles).iter().any(|r| r == required)
+    }
+
+    /// Every permission granted to the given roles, including inherited ones.
+    pub fn permissions_for(&self, roles: &[String]) -> HashSet<String> {
+        self.effective_roles(roles)
+            .iter()
+            .filter_map(|role| self.roles.get(role))
+            .flat_map(|def| def.permissions.iter().cloned())
+            .collect()
+    }
+
+    /// Check whether the given roles grant a permission.
+    pub fn grants(&self, roles: &[String], permission: &str) -> bool {
+        self.permissions_for(roles)
+            .iter()
+            .any(|granted| permission_matches(granted, permission))
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Active role hierarchy.
+    static ref ROLE_HIERARCHY: RwLock<RoleHierarchy> = RwLock::new(RoleHierarchy::default());
+}
+
+/// Replace the active role hierarchy.
+pub fn set_role_hierarchy(hierarchy: RoleHierarchy) {
+    if let Ok(mut active) = ROLE_HIERARCHY.write() {
+        *active = hierarchy;
+    }
+}
+
+/// Get a copy of the active role hierarchy.
+pub fn role_hierarchy() -> RoleHierarchy {
+    match ROLE_HIERARCHY.read() {
+        Ok(active) => active.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
 
 /// Represents a role → view mapping.
 pub struct RoleViews {
@@ -38,16 +199,21 @@ impl RoleViews {
     }
 
     /// Resolve the correct view for a user.
+    ///
+    /// The user's own roles are tried first, then inherited roles.
     pub fn resolve(&self, user: &User) -> Option<&GUIContainer> {
-        self.map.get(&user.role)
+        role_hierarchy()
+            .effective_roles(&user.roles)
+            .iter()
+            .find_map(|role| self.map.get(role))
     }
 
     /// Resolve with fallback.
-    pub fn resolve_or_default(
-        &self,
+    pub fn resolve_or_default<'a>(
+        &'a self,
         user: &User,
-        default: &GUIContainer,
-    ) -> &GUIContainer {
-        self.map.get(&user.role).unwrap_or(default)
+        default: &'a GUIContainer,
+    ) -> &'a GUIContainer {
+        self.resolve(user).unwrap_or(default)
     }
 }
@@ -0,0 +1,823 @@
+// Sentinel/src/Backend/Sessions.rs
+
+//! Sentinel Session Store
+//!
+//! Provides:
+//! - Session records with creation / last-seen timestamps
+//! - Idle timeout + absolute lifetime enforcement
+//! - Refresh tokens grouped into rotation families
+//! - A pluggable `SessionStore` trait
+//! - A thread-safe in-memory implementation
+//! - An encrypted file-backed implementation (JSON/YAML/XML)
+//! - An SQLite implementation (behind the `sql` feature)
+//!
+//! Auth uses the active store to issue and validate session tokens.
+
+use crate::Backend::Cryptography::{encrypt_aes256, decrypt_aes256, hash_sha256, random};
+use crate::Backend::DataHandler::{write_atomic, DataFormat};
+#[cfg(feature = "sql")]
+use crate::Backend::DataHandler::SQLHandler;
+
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+#[cfg(feature = "sql")]
+use std::sync::Arc;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Current UNIX time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Non-secret id of a session: the SHA-256 hash of its token.
+///
+/// Listings return this id instead of the bearer token, and it can be
+/// used to revoke the session (`SessionStore::remove_by_id`).
+pub fn session_id(token: &str) -> String {
+    hash_sha256(token)
+}
+
+/// Timeouts applied to newly created sessions.
+#[derive(Clone, Copy, Debug)]
+pub struct SessionConfig {
+    pub idle_timeout: Duration,
+    pub max_lifetime: Duration,
+    /// How long a refresh token stays usable.
+    pub refresh_lifetime: Duration,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(30 * 60),
+            max_lifetime: Duration::from_secs(12 * 60 * 60),
+            refresh_lifetime: Duration::from_secs(30 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// A single authenticated session.
+///
+/// Timestamps are UNIX seconds so sessions can be serialized as-is.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub token: String,
+    pub username: String,
+    pub created_at: u64,
+    pub last_seen: u64,
+    pub idle_timeout: u64,
+    pub max_lifetime: u64,
+    /// Refresh token family the session was issued with, if any.
+    #[serde(default)]
+    pub family: Option<String>,
+}
+
+impl Session {
+    /// Create a new session starting now.
+    pub fn new(
+        token: impl Into<String>,
+        username: impl Into<String>,
+        config: &SessionConfig,
+    ) -> Self {
+        let now = unix_now();
+
+        Self {
+            token: token.into(),
+            username: username.into(),
+            created_at: now,
+            last_seen: now,
+            idle_timeout: config.idle_timeout.as_secs(),
+            max_lifetime: config.max_lifetime.as_secs(),
+            family: None,
+        }
+    }
+
+    /// Link the session to a refresh token family.
+    pub fn with_family(mut self, family: impl Into<String>) -> Self {
+        self.family = Some(family.into());
+        self
+    }
+
+    /// Check whether the session is expired at the given time.
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        now.saturating_sub(self.last_seen) > self.idle_timeout
+            || now.saturating_sub(self.created_at) > self.max_lifetime
+    }
+
+    /// Check whether the session is expired right now.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(unix_now())
+    }
+}
+
+/// A refresh token record. Only the SHA-256 hash of the token is stored.
+///
+/// Every refresh marks the presented token as used and issues a new one
+/// in the same family; presenting a used token revokes the family.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub token_hash: String,
+    pub family: String,
+    pub username: String,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub used: bool,
+}
+
+impl RefreshToken {
+    /// Check whether the token is expired at the given time.
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Storage backend for sessions.
+///
+/// Implementations must be safe to share between threads.
+pub trait SessionStore: Send + Sync {
+    /// Timeouts used for sessions created against this store.
+    fn config(&self) -> SessionConfig;
+
+    /// Store a new session.
+    fn insert(&self, session: Session) -> Result<(), String>;
+
+    /// Look up a session without refreshing it.
+    fn get(&self, token: &str) -> Option<Session>;
+
+    /// Validate a session and slide its idle timer.
+    ///
+    /// Expired sessions are removed and `None` is returned.
+    fn touch(&self, token: &str) -> Option<Session>;
+
+    /// Remove a session.
+    fn remove(&self, token: &str) -> Option<Session>;
+
+    /// List every live session belonging to a user.
+    ///
+    /// `token` holds the session id (`session_id`), never the bearer token.
+    fn sessions_for(&self, username: &str) -> Vec<Session>;
+
+    /// Remove a session by its id (`session_id` of the token).
+    fn remove_by_id(&self, id: &str) -> Option<Session>;
+
+    /// Remove every session belonging to a user, returning how many were removed.
+    fn remove_user(&self, username: &str) -> usize;
+
+    /// Drop every expired session and refresh token, returning how many were removed.
+    fn purge_expired(&self) -> usize;
+
+    /// Store a new refresh token.
+    fn insert_refresh(&self, token: RefreshToken) -> Result<(), String>;
+
+    /// Look up a refresh token by hash.
+    fn get_refresh(&self, token_hash: &str) -> Option<RefreshToken>;
+
+    /// Mark a refresh token as used.
+    ///
+    /// Returns `false` if it was missing or already used, so two
+    /// concurrent refreshes with the same token cannot both succeed.
+    fn mark_refresh_used(&self, token_hash: &str) -> bool;
+
+    /// Remove every refresh token and session in a family, returning how many were removed.
+    fn revoke_family(&self, family: &str) -> usize;
+
+    /// Remove every refresh token belonging to a user, returning how many were removed.
+    fn remove_user_refresh(&self, username: &str) -> usize;
+}
+
+/// In-memory session store guarded by an `RwLock`.
+pub struct MemorySessionStore {
+    config: SessionConfig,
+    sessions: RwLock<HashMap<String, Session>>,
+    refresh: RwLock<HashMap<String, RefreshToken>>,
+}
+
+impl MemorySessionStore {
+    /// Create an empty store with the given timeouts.
+    pub fn new(config: SessionConfig) -> Self {
+        Self {
+            config,
+            sessions: RwLock::new(HashMap::new()),
+            refresh: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Snapshot every stored session.
+    pub fn all(&self) -> Vec<Session> {
+        match self.sessions.read() {
+            Ok(sessions) => sessions.values().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Snapshot every stored refresh token.
+    pub fn all_refresh(&self) -> Vec<RefreshToken> {
+        match self.refresh.read() {
+            Ok(tokens) => tokens.values().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn retain_refresh(&self, keep: impl Fn(&RefreshToken) -> bool) -> usize {
+        let Ok(mut tokens) = self.refresh.write() else {
+            return 0;
+        };
+        let before = tokens.len();
+
+        tokens.retain(|_, t| keep(t));
+        before - tokens.len()
+    }
+}
+
+impl Default for MemorySessionStore {
+    fn default() -> Self {
+        Self::new(SessionConfig::default())
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn config(&self) -> SessionConfig {
+        self.config
+    }
+
+    fn insert(&self, session: Session) -> Result<(), String> {
+        let mut sessions = self.sessions.write().map_err(|e| e.to_string())?;
+        sessions.insert(session.token.clone(), session);
+        Ok(())
+    }
+
+    fn get(&self, token: &str) -> Option<Session> {
+        let sessions = self.sessions.read().ok()?;
+        sessions.get(token).cloned()
+    }
+
+    fn touch(&self, token: &str) -> Option<Session> {
+        let mut sessions = self.sessions.write().ok()?;
+        let now = unix_now();
+
+        if sessions.get(token)?.is_expired_at(now) {
+            sessions.remove(token);
+            return None;
+        }
+
+        let session = sessions.get_mut(token)?;
+        session.last_seen = now;
+        Some(session.clone())
+    }
+
+    fn remove(&self, token: &str) -> Option<Session> {
+        let mut sessions = self.sessions.write().ok()?;
+        sessions.remove(token)
+    }
+
+    fn sessions_for(&self, username: &str) -> Vec<Session> {
+        let Ok(sessions) = self.sessions.read() else {
+            return Vec::new();
+        };
+        let now = unix_now();
+
+        sessions
+            .values()
+            .filter(|s| s.username == username && !s.is_expired_at(now))
+            .map(|s| Session {
+                token: session_id(&s.token),
+                ..s.clone()
+            })
+            .collect()
+    }
+
+    fn remove_by_id(&self, id: &str) -> Option<Session> {
+        let mut sessions = self.sessions.write().ok()?;
+        let token = sessions.keys().find(|token| session_id(token) == id)?.clone();
+        sessions.remove(&token)
+    }
+
+    fn remove_user(&self, username: &str) -> usize {
+        let Ok(mut sessions) = self.sessions.write() else {
+            return 0;
+        };
+        let before = sessions.len();
+
+        sessions.retain(|_, s| s.username != username);
+        before - sessions.len()
+    }
+
+    fn purge_expired(&self) -> usize {
+        let Ok(mut sessions) = self.sessions.write() else {
+            return 0;
+        };
+        let now = unix_now();
+        let before = sessions.len();
+
+        sessions.retain(|_, s| !s.is_expired_at(now));
+        let removed = before - sessions.len();
+        drop(sessions);
+
+        removed + self.retain_refresh(|t| !t.is_expired_at(now))
+    }
+
+    fn insert_refresh(&self, token: RefreshToken) -> Result<(), String> {
+        let mut tokens = self.refresh.write().map_err(|e| e.to_string())?;
+        tokens.insert(token.token_hash.clone(), token);
+        Ok(())
+    }
+
+    fn get_refresh(&self, token_hash: &str) -> Option<RefreshToken> {
+        self.refresh.read().ok()?.get(token_hash).cloned()
+    }
+
+    fn mark_refresh_used(&self, token_hash: &str) -> bool {
+        let Ok(mut tokens) = self.refresh.write() else {
+            return false;
+        };
+
+        match tokens.get_mut(token_hash) {
+            Some(token) if !token.used => {
+                token.used = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn revoke_family(&self, family: &str) -> usize {
+        let sessions = match self.sessions.write() {
+            Ok(mut sessions) => {
+                let before = sessions.len();
+                sessions.retain(|_, s| s.family.as_deref() != Some(family));
+                before - sessions.len()
+            }
+            Err(_) => 0,
+        };
+
+        sessions + self.retain_refresh(|t| t.family != family)
+    }
+
+    fn remove_user_refresh(&self, username: &str) -> usize {
+        self.retain_refresh(|t| t.username != username)
+    }
+}
+
+/// On-disk layout of a session file.
+#[derive(Serialize, Deserialize)]
+struct SessionFile {
+    #[serde(default)]
+    session: Vec<Session>,
+    #[serde(default)]
+    refresh: Vec<RefreshToken>,
+}
+
+/// Minimum seconds between file rewrites caused only by `touch`.
+const TOUCH_FLUSH_INTERVAL: u64 = 60;
+
+/// Session store persisted to a single encrypted file.
+///
+/// Sessions are serialized through the DataHandler handlers and
+/// encrypted with AES-256-GCM. The file holds the 12-byte nonce
+/// followed by the ciphertext, and is replaced atomically on write.
+///
+/// `last_seen` updates are written at most every `TOUCH_FLUSH_INTERVAL`
+/// seconds, so after a crash a session may look idle for up to that long.
+pub struct FileSessionStore {
+    path: PathBuf,
+    format: DataFormat,
+    key: [u8; 32],
+    inner: MemorySessionStore,
+    write_lock: Mutex<()>,
+    last_flush: Mutex<u64>,
+}
+
+impl FileSessionStore {
+    /// Open a session file, loading any sessions it already holds.
+    pub fn open(
+        path: impl Into<PathBuf>,
+        format: DataFormat,
+        key: [u8; 32],
+        config: SessionConfig,
+    ) -> Result<Self, String> {
+        let store = Self {
+            path: path.into(),
+            format,
+            key,
+            inner: MemorySessionStore::new(config),
+            write_lock: Mutex::new(()),
+            last_flush: Mutex::new(0),
+        };
+
+        if store.path.exists() {
+            let file = store.load()?;
+            let now = unix_now();
+
+            for session in file.session {
+                if !session.is_expired_at(now) {
+                    store.inner.insert(session)?;
+                }
+            }
+            for token in file.refresh {
+                if !token.is_expired_at(now) {
+                    store.inner.insert_refresh(token)?;
+                }
+            }
+        }
+
+        Ok(store)
+    }
+
+    /// Read and decrypt the session file.
+    fn load(&self) -> Result<SessionFile, String> {
+        let bytes = fs::read(&self.path).map_err(|e| e.to_string())?;
+        if bytes.len() < 12 {
+            return Err("Session file is truncated".into());
+        }
+
+        let (nonce, ciphertext) = bytes.split_at(12);
+        let nonce: [u8; 12] = nonce.try_into().map_err(|_| "Invalid nonce".to_string())?;
+        let plaintext = decrypt_aes256(&self.key, &nonce, ciphertext)?;
+
+        Ok(self.format.decode(&plaintext)?)
+    }
+
+    /// Encrypt and write the current sessions to disk.
+    fn flush(&self) -> Result<(), String> {
+        let _guard = self.write_lock.lock().map_err(|e| e.to_string())?;
+
+        let file = SessionFile {
+            session: self.inner.all(),
+            refresh: self.inner.all_refresh(),
+        };
+        let plaintext = self.format.encode(&file)?;
+
+        let mut nonce = [0u8; 12];
+        random::fill(&mut nonce);
+        let ciphertext = encrypt_aes256(&self.key, &nonce, &plaintext)?;
+
+        let mut bytes = nonce.to_vec();
+        bytes.extend_from_slice(&ciphertext);
+        write_atomic(&self.path, &bytes)?;
+
+        if let Ok(mut last_flush) = self.last_flush.lock() {
+            *last_flush = unix_now();
+        }
+        Ok(())
+    }
+
+    /// Flush from a method that cannot return an error, logging failures.
+    fn persist(&self) {
+        if let Err(e) = self.flush() {
+            log::error!("Failed to write session file '{}': {}", self.path.display(), e);
+        }
+    }
+
+    /// Whether a `touch`-only change is due to be written.
+    fn touch_flush_due(&self) -> bool {
+        self.last_flush
+            .lock()
+            .map(|last| unix_now().saturating_sub(*last) >= TOUCH_FLUSH_INTERVAL)
+            .unwrap_or(true)
+    }
+}
+
+impl SessionStore for FileSessionStore {
+    fn config(&self) -> SessionConfig {
+        self.inner.config()
+    }
+
+    fn insert(&self, session: Session) -> Result<(), String> {
+        self.inner.insert(session)?;
+        self.flush()
+    }
+
+    fn get(&self, token: &str) -> Option<Session> {
+        self.inner.get(token)
+    }
+
+    fn touch(&self, token: &str) -> Option<Session> {
+        let before = self.inner.get(token)?;
+        let touched = self.inner.touch(token);
+
+        // Expiry removes the session and is written right away; plain
+        // last_seen updates are batched.
+        match &touched {
+            None => self.persist(),
+            Some(s) if s.last_seen != before.last_seen && self.touch_flush_due() => self.persist(),
+            Some(_) => {}
+        }
+
+        touched
+    }
+
+    fn remove(&self, token: &str) -> Option<Session> {
+        let removed = self.inner.remove(token);
+        if removed.is_some() {
+            self.persist();
+        }
+        removed
+    }
+
+    fn sessions_for(&self, username: &str) -> Vec<Session> {
+        self.inner.sessions_for(username)
+    }
+
+    fn remove_by_id(&self, id: &str) -> Option<Session> {
+        let removed = self.inner.remove_by_id(id);
+        if removed.is_some() {
+            self.persist();
+        }
+        removed
+    }
+
+    fn remove_user(&self, username: &str) -> usize {
+        let removed = self.inner.remove_user(username);
+        if removed > 0 {
+            self.persist();
+        }
+        removed
+    }
+
+    fn purge_expired(&self) -> usize {
+        let removed = self.inner.purge_expired();
+        if removed > 0 {
+            self.persist();
+        }
+        removed
+    }
+
+    fn insert_refresh(&self, token: RefreshToken) -> Result<(), String> {
+        self.inner.insert_refresh(token)?;
+        self.flush()
+    }
+
+    fn get_refresh(&self, token_hash: &str) -> Option<RefreshToken> {
+        self.inner.get_refresh(token_hash)
+    }
+
+    fn mark_refresh_used(&self, token_hash: &str) -> bool {
+        let marked = self.inner.mark_refresh_used(token_hash);
+        if marked {
+            self.persist();
+        }
+        marked
+    }
+
+    fn revoke_family(&self, family: &str) -> usize {
+        let removed = self.inner.revoke_family(family);
+        if removed > 0 {
+            self.persist();
+        }
+        removed
+    }
+
+    fn remove_user_refresh(&self, username: &str) -> usize {
+        let removed = self.inner.remove_user_refresh(username);
+        if removed > 0 {
+            self.persist();
+        }
+        removed
+    }
+}
+
+/// Session store backed by an SQLite database.
+///
+/// Rows are keyed by the session id (SHA-256 of the token), so the
+/// database never holds usable bearer tokens.
+///
+/// Queries run through an `SQLHandler`, which can be shared with the
+/// user repository and `Work::with_sql`. Calls block, so this store
+/// must not be used from inside an async task.
+#[cfg(feature = "sql")]
+pub struct SqliteSessionStore {
+    config: SessionConfig,
+    sql: Arc<SQLHandler>,
+}
+
+/// A refresh token row (`used` is stored as an integer).
+#[cfg(feature = "sql")]
+#[derive(Deserialize)]
+struct RefreshRow {
+    token_hash: String,
+    family: String,
+    username: String,
+    created_at: u64,
+    expires_at: u64,
+    used: i64,
+}
+
+#[cfg(feature = "sql")]
+const SESSION_COLUMNS: &str =
+    "token_hash AS token, username, created_at, last_seen, idle_timeout, max_lifetime, family";
+
+#[cfg(feature = "sql")]
+impl SqliteSessionStore {
+    /// Connect to a database (e.g. `sqlite://sessions.db?mode=rwc`)
+    /// and create the sessions table if needed.
+    pub fn connect(url: &str, config: SessionConfig) -> Result<Self, String> {
+        Self::with_handler(Arc::new(SQLHandler::connect(url)?), config)
+    }
+
+    /// Use an existing SQL handler and create the sessions table if needed.
+    pub fn with_handler(sql: Arc<SQLHandler>, config: SessionConfig) -> Result<Self, String> {
+        sql.execute(
+            "CREATE TABLE IF NOT EXISTS sentinel_sessions (
+                token_hash TEXT PRIMARY KEY,
+                username TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                idle_timeout INTEGER NOT NULL,
+                max_lifetime INTEGER NOT NULL,
+                family TEXT
+            )",
+            &[],
+        )?;
+
+        sql.execute(
+            "CREATE TABLE IF NOT EXISTS sentinel_refresh_tokens (
+                token_hash TEXT PRIMARY KEY,
+                family TEXT NOT NULL,
+                username TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                used INTEGER NOT NULL
+            )",
+            &[],
+        )?;
+
+        Ok(Self { config, sql })
+    }
+
+    fn execute_where(&self, sql: &str, value: &str) -> usize {
+        self.sql
+            .execute(sql, &[value.into()])
+            .map(|rows| rows as usize)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(feature = "sql")]
+impl SessionStore for SqliteSessionStore {
+    fn config(&self) -> SessionConfig {
+        self.config
+    }
+
+    fn insert(&self, session: Session) -> Result<(), String> {
+        self.sql.execute(
+            "INSERT OR REPLACE INTO sentinel_sessions VALUES (?, ?, ?, ?, ?, ?, ?)",
+            &[
+                session_id(&session.token).into(),
+                session.username.into(),
+                (session.created_at as i64).into(),
+                (session.last_seen as i64).into(),
+                (session.idle_timeout as i64).into(),
+                (session.max_lifetime as i64).into(),
+                session.family.into(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, token: &str) -> Option<Session> {
+        self.sql
+            .fetch_optional::<Session>(
+                &format!("SELECT {} FROM sentinel_sessions WHERE token_hash = ?", SESSION_COLUMNS),
+                &[session_id(token).into()],
+            )
+            .ok()
+            .flatten()
+            .map(|session| Session {
+                token: token.to_string(),
+                ..session
+            })
+    }
+
+    fn touch(&self, token: &str) -> Option<Session> {
+        let mut session = self.get(token)?;
+        let now = unix_now();
+
+        if session.is_expired_at(now) {
+            self.remove(token);
+            return None;
+        }
+
+        self.sql
+            .execute(
+                "UPDATE sentinel_sessions SET last_seen = ? WHERE token_hash = ?",
+                &[(now as i64).into(), session_id(token).into()],
+            )
+            .ok()?;
+
+        session.last_seen = now;
+        Some(session)
+    }
+
+    fn remove(&self, token: &str) -> Option<Session> {
+        let session = self.get(token)?;
+        self.execute_where("DELETE FROM sentinel_sessions WHERE token_hash = ?", &session_id(token));
+        Some(session)
+    }
+
+    fn sessions_for(&self, username: &str) -> Vec<Session> {
+        let now = unix_now();
+
+        self.sql
+            .fetch_all::<Session>(
+                &format!("SELECT {} FROM sentinel_sessions WHERE username = ?", SESSION_COLUMNS),
+                &[username.into()],
+            )
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| !s.is_expired_at(now))
+            .collect()
+    }
+
+    fn remove_by_id(&self, id: &str) -> Option<Session> {
+        let session = self
+            .sql
+            .fetch_optional::<Session>(
+                &format!("SELECT {} FROM sentinel_sessions WHERE token_hash = ?", SESSION_COLUMNS),
+                &[id.into()],
+            )
+            .ok()
+            .flatten()?;
+        self.execute_where("DELETE FROM sentinel_sessions WHERE token_hash = ?", id);
+        Some(session)
+    }
+
+    fn remove_user(&self, username: &str) -> usize {
+        self.execute_where("DELETE FROM sentinel_sessions WHERE username = ?", username)
+    }
+
+    fn purge_expired(&self) -> usize {
+        let now = unix_now() as i64;
+
+        let sessions = self
+            .sql
+            .execute(
+                "DELETE FROM sentinel_sessions
+                 WHERE ? - last_seen > idle_timeout OR ? - created_at > max_lifetime",
+                &[now.into(), now.into()],
+            )
+            .unwrap_or(0) as usize;
+
+        let refresh = self
+            .sql
+            .execute("DELETE FROM sentinel_refresh_tokens WHERE expires_at <= ?", &[now.into()])
+            .unwrap_or(0) as usize;
+
+        sessions + refresh
+    }
+
+    fn insert_refresh(&self, token: RefreshToken) -> Result<(), String> {
+        self.sql.execute(
+            "INSERT OR REPLACE INTO sentinel_refresh_tokens VALUES (?, ?, ?, ?, ?, ?)",
+            &[
+                token.token_hash.into(),
+                token.family.into(),
+                token.username.into(),
+                (token.created_at as i64).into(),
+                (token.expires_at as i64).into(),
+                token.used.into(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get_refresh(&self, token_hash: &str) -> Option<RefreshToken> {
+        self.sql
+            .fetch_optional::<RefreshRow>(
+                "SELECT token_hash, family, username, created_at, expires_at, used
+                 FROM sentinel_refresh_tokens WHERE token_hash = ?",
+                &[token_hash.into()],
+            )
+            .ok()
+            .flatten()
+            .map(|row| RefreshToken {
+                token_hash: row.token_hash,
+                family: row.family,
+                username: row.username,
+                created_at: row.created_at,
+                expires_at: row.expires_at,
+                used: row.used != 0,
+            })
+    }
+
+    fn mark_refresh_used(&self, token_hash: &str) -> bool {
+        self.execute_where(
+            "UPDATE sentinel_refresh_tokens SET used = 1 WHERE token_hash = ? AND used = 0",
+            token_hash,
+        ) == 1
+    }
+
+    fn revoke_family(&self, family: &str) -> usize {
+        self.execute_where("DELETE FROM sentinel_sessions WHERE family = ?", family)
+            + self.execute_where("DELETE FROM sentinel_refresh_tokens WHERE family = ?", family)
+    }
+
+    fn remove_user_refresh(&self, username: &str) -> usize {
+        self.execute_where("DELETE FROM sentinel_refresh_tokens WHERE username = ?", username)
+    }
+}
@@ -0,0 +1,365 @@
+// Sentinel/src/Backend/Tokens.rs
+
+//! Sentinel Signed Tokens
+//!
+//! Provides:
+//! - A process-wide token signing secret
+//! - Signed, expiring action tokens (password reset, account verification)
+//! - Stateless session tokens (HMAC-signed or AES-GCM encrypted)
+//! - A pluggable revocation list for stateless tokens
+//!
+//! Signed tokens have the form `<base64url(claims)>.<base64url(hmac)>`.
+//! Action tokens carry a `stamp` derived from the user's current state,
+//! so they stop working once they have been used.
+//!
+//! Session tokens are prefixed so they can be told apart from opaque ones:
+//! `st.<claims>.<mac>` (signed) or `et.<base64url(nonce + ciphertext)>` (encrypted).
+
+use crate::Backend::Cryptography::{hmac_sha256, verify_hmac_sha256, encrypt_aes256, decrypt_aes256, random};
+use crate::Backend::Sessions::unix_now;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+lazy_static::lazy_static! {
+    /// Secret used to sign tokens (random per process unless replaced).
+    static ref TOKEN_SECRET: RwLock<Vec<u8>> = RwLock::new(random_secret());
+
+    /// Active revocation list (in-memory unless replaced).
+    static ref REVOCATIONS: RwLock<Arc<dyn RevocationList>> =
+        RwLock::new(Arc::new(MemoryRevocationList::default()));
+}
+
+fn random_secret() -> Vec<u8> {
+    random::bytes(32)
+}
+
+/// Replace the token signing secret.
+///
+/// Set the same secret in every process that must accept the tokens.
+pub fn set_token_secret(secret: &[u8]) {
+    if let Ok(mut active) = TOKEN_SECRET.write() {
+        *active = secret.to_vec();
+    }
+}
+
+fn token_secret() -> Vec<u8> {
+    match TOKEN_SECRET.read() {
+        Ok(secret) => secret.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// AES-256 key for encrypted session tokens, derived from the token secret.
+fn token_encryption_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hmac_sha256(&token_secret(), b"sentinel-session-token-encryption"));
+    key
+}
+
+/// `typ` claim of action tokens.
+pub const TYP_ACTION: &str = "action";
+/// `typ` claim of stateless session tokens.
+pub const TYP_SESSION: &str = "session";
+
+/// Claims carried by an action token.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActionClaims {
+    /// Token type (`TYP_ACTION`), so session claims signed with the
+    /// same secret are never accepted as action claims.
+    pub typ: String,
+    /// What the token may be used for (e.g. `"password_reset"`).
+    pub purpose: String,
+    /// Username the token was issued for.
+    pub sub: String,
+    /// Fingerprint of the user state the token is bound to.
+    pub stamp: String,
+    /// Expiry (UNIX seconds).
+    pub exp: u64,
+    /// Random token id.
+    pub jti: String,
+}
+
+/// Sign arbitrary claims as `<payload>.<mac>`.
+pub fn sign_claims<T: Serialize>(claims: &T) -> Result<String, String> {
+    let payload = serde_json::to_vec(claims).map_err(|e| e.to_string())?;
+    let encoded = URL_SAFE_NO_PAD.encode(payload);
+    let mac = hmac_sha256(&token_secret(), encoded.as_bytes());
+
+    Ok(format!("{}.{}", encoded, URL_SAFE_NO_PAD.encode(mac)))
+}
+
+/// Check the signature of a `<payload>.<mac>` token and decode its claims.
+pub fn verify_signed_claims<T>(token: &str) -> Result<T, String>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let (encoded, mac) = token
+        .split_once('.')
+        .ok_or_else(|| "Malformed token".to_string())?;
+
+    let mac = URL_SAFE_NO_PAD.decode(mac).map_err(|_| "Malformed token".to_string())?;
+    if !verify_hmac_sha256(&token_secret(), encoded.as_bytes(), &mac) {
+        return Err("Invalid token signature".into());
+    }
+
+    let payload = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|_| "Malformed token".to_string())?;
+    serde_json::from_slice(&payload).map_err(|e| e.to_string())
+}
+
+/// Issue a signed, expiring action token.
+pub fn issue_action_token(
+    purpose: &str,
+    username: &str,
+    stamp: &str,
+    ttl_seconds: u64,
+) -> Result<String, String> {
+    let jti = random::bytes(16);
+
+    sign_claims(&ActionClaims {
+        typ: TYP_ACTION.into(),
+        purpose: purpose.into(),
+        sub: username.into(),
+        stamp: stamp.into(),
+        exp: unix_now() + ttl_seconds,
+        jti: URL_SAFE_NO_PAD.encode(jti),
+    })
+}
+
+/// Verify an action token's signature, type, purpose and expiry.
+///
+/// The caller must still compare `stamp` against the user's state.
+pub fn verify_action_token(token: &str, purpose: &str) -> Result<ActionClaims, String> {
+    let claims: ActionClaims = verify_signed_claims(token.trim())?;
+
+    if claims.typ != TYP_ACTION {
+        return Err("Not an action token".into());
+    }
+    if claims.purpose != purpose {
+        return Err("Token was issued for a different purpose".into());
+    }
+    if claims.exp <= unix_now() {
+        return Err("Token expired".into());
+    }
+
+    Ok(claims)
+}
+
+// -------------------------
+// STATELESS SESSION TOKENS
+// -------------------------
+
+const SIGNED_PREFIX: &str = "st.";
+const ENCRYPTED_PREFIX: &str = "et.";
+
+/// How a stateless session token is protected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenProtection {
+    /// Claims are readable by the client but signed with HMAC-SHA256.
+    Signed,
+    /// Claims are encrypted and authenticated with AES-256-GCM.
+    Encrypted,
+}
+
+/// Claims carried by a stateless session token.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionClaims {
+    /// Token type (`TYP_SESSION`), so action claims signed with the
+    /// same secret are never accepted as session claims.
+    pub typ: String,
+    /// Username the token was issued for.
+    pub sub: String,
+    /// Roles held at issue time.
+    pub roles: Vec<String>,
+    /// Issued at (UNIX seconds).
+    pub iat: u64,
+    /// Expiry (UNIX seconds).
+    pub exp: u64,
+    /// Random token id (used for revocation).
+    pub jti: String,
+    /// Refresh token family the token was issued with, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fam: Option<String>,
+}
+
+/// Check if a token looks like a stateless session token.
+pub fn is_stateless_token(token: &str) -> bool {
+    token.starts_with(SIGNED_PREFIX) || token.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Issue a stateless session token, optionally linked to a refresh token family.
+pub fn issue_session_token(
+    protection: TokenProtection,
+    username: &str,
+    roles: &[String],
+    ttl_seconds: u64,
+    family: Option<&str>,
+) -> Result<String, String> {
+    let jti = random::bytes(16);
+
+    let now = unix_now();
+    let claims = SessionClaims {
+        typ: TYP_SESSION.into(),
+        sub: username.into(),
+        roles: roles.to_vec(),
+        iat: now,
+        exp: now + ttl_seconds,
+        jti: URL_SAFE_NO_PAD.encode(jti),
+        fam: family.map(str::to_string),
+    };
+
+    match protection {
+        TokenProtection::Signed => Ok(format!("{}{}", SIGNED_PREFIX, sign_claims(&claims)?)),
+        TokenProtection::Encrypted => {
+            let payload = serde_json::to_string(&claims).map_err(|e| e.to_string())?;
+            let mut nonce = [0u8; 12];
+            random::fill(&mut nonce);
+
+            let mut sealed = nonce.to_vec();
+            sealed.extend(encrypt_aes256(&token_encryption_key(), &nonce, &payload)?);
+            Ok(format!("{}{}", ENCRYPTED_PREFIX, URL_SAFE_NO_PAD.encode(sealed)))
+        }
+    }
+}
+
+/// Verify a stateless session token's signature, type, expiry and revocation status.
+pub fn verify_session_token(token: &str) -> Result<SessionClaims, String> {
+    let claims: SessionClaims = if let Some(signed) = token.strip_prefix(SIGNED_PREFIX) {
+        verify_signed_claims(signed)?
+    } else if let Some(encrypted) = token.strip_prefix(ENCRYPTED_PREFIX) {
+        let sealed = URL_SAFE_NO_PAD
+            .decode(encrypted)
+            .map_err(|_| "Malformed token".to_string())?;
+        if sealed.len() < 12 {
+            return Err("Malformed token".into());
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let mut nonce_bytes = [0u8; 12];
+        nonce_bytes.copy_from_slice(nonce);
+
+        let payload = decrypt_aes256(&token_encryption_key(), &nonce_bytes, ciphertext)
+            .map_err(|_| "Invalid token".to_string())?;
+        serde_json::from_str(&payload).map_err(|e| e.to_string())?
+    } else {
+        return Err("Not a stateless session token".into());
+    };
+
+    if claims.typ != TYP_SESSION {
+        return Err("Not a session token".into());
+    }
+    if claims.exp <= unix_now() {
+        return Err("Session expired".into());
+    }
+    if revocation_list().is_revoked(&claims) {
+        return Err("Session revoked".into());
+    }
+
+    Ok(claims)
+}
+
+/// Revoke a single stateless session token.
+///
+/// Returns `false` if the token was not valid to begin with.
+pub fn revoke_session_token(token: &str) -> bool {
+    match verify_session_token(token) {
+        Ok(claims) => {
+            revocation_list().revoke_id(&claims.jti, claims.exp);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+// -------------------------
+// REVOCATION LIST
+// -------------------------
+
+/// Storage for revoked stateless tokens.
+///
+/// Share one implementation between processes that accept the same tokens.
+pub trait RevocationList: Send + Sync {
+    /// Revoke one token id until its expiry.
+    fn revoke_id(&self, jti: &str, exp: u64);
+    /// Revoke every token of a user issued before `issued_before`.
+    fn revoke_subject(&self, username: &str, issued_before: u64);
+    /// Revoke every token of a refresh token family until `until` (UNIX seconds).
+    fn revoke_family(&self, family: &str, until: u64);
+    /// Check whether a token has been revoked.
+    fn is_revoked(&self, claims: &SessionClaims) -> bool;
+}
+
+/// Thread-safe in-memory revocation list.
+#[derive(Default)]
+pub struct MemoryRevocationList {
+    ids: RwLock<HashMap<String, u64>>,
+    subjects: RwLock<HashMap<String, u64>>,
+    families: RwLock<HashMap<String, u64>>,
+}
+
+impl RevocationList for MemoryRevocationList {
+    fn revoke_id(&self, jti: &str, exp: u64) {
+        if let Ok(mut ids) = self.ids.write() {
+            let now = unix_now();
+            ids.retain(|_, e| *e > now);
+            ids.insert(jti.into(), exp);
+        }
+    }
+
+    fn revoke_subject(&self, username: &str, issued_before: u64) {
+        if let Ok(mut subjects) = self.subjects.write() {
+            subjects.insert(username.into(), issued_before);
+        }
+    }
+
+    fn revoke_family(&self, family: &str, until: u64) {
+        if let Ok(mut families) = self.families.write() {
+            let now = unix_now();
+            families.retain(|_, e| *e > now);
+            families.insert(family.into(), until);
+        }
+    }
+
+    fn is_revoked(&self, claims: &SessionClaims) -> bool {
+        let by_family = match &claims.fam {
+            Some(family) => self
+                .families
+                .read()
+                .map(|f| f.contains_key(family))
+                .unwrap_or(true),
+            None => false,
+        };
+        let by_id = self
+            .ids
+            .read()
+            .map(|ids| ids.contains_key(&claims.jti))
+            .unwrap_or(true);
+        let by_subject = self
+            .subjects
+            .read()
+            .map(|s| s.get(&claims.sub).map(|cutoff| claims.iat < *cutoff).unwrap_or(false))
+            .unwrap_or(true);
+
+        by_id || by_subject || by_family
+    }
+}
+
+/// Replace the active revocation list.
+pub fn set_revocation_list(list: Arc<dyn RevocationList>) {
+    if let Ok(mut active) = REVOCATIONS.write() {
+        *active = list;
+    }
+}
+
+/// Get the active revocation list.
+pub fn revocation_list() -> Arc<dyn RevocationList> {
+    match REVOCATIONS.read() {
+        Ok(list) => list.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
@@ -0,0 +1,299 @@
+// Sentinel/src/Backend/Users.rs
+
+//! Sentinel User Repository
+//!
+//! Provides:
+//! - A `UserRepository` trait (create / find / update / delete / list)
+//! - A thread-safe in-memory implementation
+//! - A JSON file implementation
+//! - An SQLite implementation (behind the `sql` feature)
+//!
+//! Usernames are unique and compared case-insensitively.
+
+use crate::Backend::Auth::User;
+use crate::Backend::DataHandler::{write_atomic, CRUD, JSONHandler};
+#[cfg(feature = "sql")]
+use crate::Backend::DataHandler::SQLHandler;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+#[cfg(feature = "sql")]
+use std::sync::Arc;
+
+/// Normalize a username into its lookup key.
+fn user_key(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+/// Storage backend for users.
+pub trait UserRepository: Send + Sync {
+    /// Store a new user. Fails if the username is already taken.
+    fn create(&self, user: &User) -> Result<(), String>;
+
+    /// Look up a user by username.
+    fn find_by_username(&self, username: &str) -> Result<Option<User>, String>;
+
+    /// Replace an existing user. Fails if the user does not exist.
+    fn update(&self, user: &User) -> Result<(), String>;
+
+    /// Delete a user. Returns `false` if the user did not exist.
+    fn delete(&self, username: &str) -> Result<bool, String>;
+
+    /// List every stored user.
+    fn list(&self) -> Result<Vec<User>, String>;
+
+    /// Check whether a username is already taken.
+    fn exists(&self, username: &str) -> Result<bool, String> {
+        Ok(self.find_by_username(username)?.is_some())
+    }
+}
+
+/// In-memory user repository guarded by an `RwLock`.
+pub struct MemoryUserRepository {
+    users: RwLock<HashMap<String, User>>,
+}
+
+impl MemoryUserRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self {
+            users: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create a repository pre-filled with users.
+    pub fn with_users(users: Vec<User>) -> Result<Self, String> {
+        let repo = Self::new();
+        for user in users {
+            repo.create(&user)?;
+        }
+        Ok(repo)
+    }
+}
+
+impl Default for MemoryUserRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UserRepository for MemoryUserRepository {
+    fn create(&self, user: &User) -> Result<(), String> {
+        let mut users = self.users.write().map_err(|e| e.to_string())?;
+        let key = user_key(&user.username);
+
+        if users.contains_key(&key) {
+            return Err(format!("Username '{}' already exists", user.username));
+        }
+
+        users.insert(key, user.clone());
+        Ok(())
+    }
+
+    fn find_by_username(&self, username: &str) -> Result<Option<User>, String> {
+        let users = self.users.read().map_err(|e| e.to_string())?;
+        Ok(users.get(&user_key(username)).cloned())
+    }
+
+    fn update(&self, user: &User) -> Result<(), String> {
+        let mut users = self.users.write().map_err(|e| e.to_string())?;
+
+        match users.get_mut(&user_key(&user.username)) {
+            Some(existing) => {
+                *existing = user.clone();
+                Ok(())
+            }
+            None => Err(format!("User '{}' not found", user.username)),
+        }
+    }
+
+    fn delete(&self, username: &str) -> Result<bool, String> {
+        let mut users = self.users.write().map_err(|e| e.to_string())?;
+        Ok(users.remove(&user_key(username)).is_some())
+    }
+
+    fn list(&self) -> Result<Vec<User>, String> {
+        let users = self.users.read().map_err(|e| e.to_string())?;
+        Ok(users.values().cloned().collect())
+    }
+}
+
+/// User repository persisted to a single JSON file.
+///
+/// The whole file is rewritten atomically (temp file + rename) after every change.
+/// A change that cannot be written is rolled back in memory as well.
+pub struct JsonUserRepository {
+    path: PathBuf,
+    inner: MemoryUserRepository,
+}
+
+impl JsonUserRepository {
+    /// Open a JSON user file, loading any users it already holds.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+
+        let users: Vec<User> = if path.exists() {
+            let json = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            JSONHandler.read(&json)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path,
+            inner: MemoryUserRepository::with_users(users)?,
+        })
+    }
+
+    /// Write every user back to disk.
+    fn flush(&self) -> Result<(), String> {
+        let mut users = self.inner.list()?;
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+
+        let json = JSONHandler.create(&users)?;
+        Ok(write_atomic(&self.path, json.as_bytes())?)
+    }
+}
+
+impl UserRepository for JsonUserRepository {
+    fn create(&self, user: &User) -> Result<(), String> {
+        self.inner.create(user)?;
+        self.flush().inspect_err(|_| {
+            let _ = self.inner.delete(&user.username);
+        })
+    }
+
+    fn find_by_username(&self, username: &str) -> Result<Option<User>, String> {
+        self.inner.find_by_username(username)
+    }
+
+    fn update(&self, user: &User) -> Result<(), String> {
+        let previous = self
+            .inner
+            .find_by_username(&user.username)?
+            .ok_or_else(|| format!("User '{}' not found", user.username))?;
+
+        self.inner.update(user)?;
+        self.flush().inspect_err(|_| {
+            let _ = self.inner.update(&previous);
+        })
+    }
+
+    fn delete(&self, username: &str) -> Result<bool, String> {
+        let Some(previous) = self.inner.find_by_username(username)? else {
+            return Ok(false);
+        };
+
+        self.inner.delete(username)?;
+        self.flush().inspect_err(|_| {
+            let _ = self.inner.create(&previous);
+        })?;
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<Vec<User>, String> {
+        self.inner.list()
+    }
+}
+
+/// User repository backed by an SQLite database.
+///
+/// Each user is stored as a JSON record keyed by username. Queries run
+/// through an `SQLHandler`, which can be shared with the session store
+/// and `Work::with_sql`. Calls block, so this repository must not be
+/// used from inside an async task.
+#[cfg(feature = "sql")]
+pub struct SqliteUserRepository {
+    sql: Arc<SQLHandler>,
+}
+
+/// A stored user record.
+#[cfg(feature = "sql")]
+#[derive(serde::Deserialize)]
+struct UserRow {
+    record: String,
+}
+
+#[cfg(feature = "sql")]
+impl SqliteUserRepository {
+    /// Connect to a database (e.g. `sqlite://users.db?mode=rwc`)
+    /// and create the users table if needed.
+    pub fn connect(url: &str) -> Result<Self, String> {
+        Self::with_handler(Arc::new(SQLHandler::connect(url)?))
+    }
+
+    /// Use an existing SQL handler and create the users table if needed.
+    pub fn with_handler(sql: Arc<SQLHandler>) -> Result<Self, String> {
+        sql.execute(
+            "CREATE TABLE IF NOT EXISTS sentinel_users (
+                username TEXT PRIMARY KEY,
+                record TEXT NOT NULL
+            )",
+            &[],
+        )?;
+
+        Ok(Self { sql })
+    }
+}
+
+#[cfg(feature = "sql")]
+impl UserRepository for SqliteUserRepository {
+    fn create(&self, user: &User) -> Result<(), String> {
+        if self.exists(&user.username)? {
+            return Err(format!("Username '{}' already exists", user.username));
+        }
+
+        let record = JSONHandler.create(user)?;
+        self.sql.execute(
+            "INSERT INTO sentinel_users (username, record) VALUES (?, ?)",
+            &[user_key(&user.username).into(), record.into()],
+        )?;
+        Ok(())
+    }
+
+    fn find_by_username(&self, username: &str) -> Result<Option<User>, String> {
+        let row: Option<UserRow> = self.sql.fetch_optional(
+            "SELECT record FROM sentinel_users WHERE username = ?",
+            &[user_key(username).into()],
+        )?;
+
+        match row {
+            Some(row) => Ok(Some(JSONHandler.read(&row.record)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn update(&self, user: &User) -> Result<(), String> {
+        let record = JSONHandler.create(user)?;
+        let affected = self.sql.execute(
+            "UPDATE sentinel_users SET record = ? WHERE username = ?",
+            &[record.into(), user_key(&user.username).into()],
+        )?;
+
+        if affected == 0 {
+            return Err(format!("User '{}' not found", user.username));
+        }
+        Ok(())
+    }
+
+    fn delete(&self, username: &str) -> Result<bool, String> {
+        let affected = self.sql.execute(
+            "DELETE FROM sentinel_users WHERE username = ?",
+            &[user_key(username).into()],
+        )?;
+
+        Ok(affected > 0)
+    }
+
+    fn list(&self) -> Result<Vec<User>, String> {
+        let rows: Vec<UserRow> = self
+            .sql
+            .fetch_all("SELECT record FROM sentinel_users ORDER BY username", &[])?;
+
+        rows.into_iter()
+            .map(|row| Ok(JSONHandler.read(&row.record)?))
+            .collect()
+    }
+}
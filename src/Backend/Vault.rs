@@ -0,0 +1,294 @@
+// Sentinel/src/Backend/Vault.rs
+
+//! Sentinel Secrets Vault
+//!
+//! Provides:
+//! - Named secrets stored in a single encrypted file
+//! - A master key from a passphrase, an environment variable or raw bytes
+//! - Add / rotate / remove / list operations
+//! - A CLI-style command interface (`add`, `rotate`, `remove`, `get`, `list`)
+//!
+//! The file is JSON holding the KDF salt and a sealed envelope
+//! (AES-256-GCM) of the secrets. It is rewritten atomically on every change.
+
+use crate::Backend::Cryptography::{
+    decode_base64, derive_key_argon2id, encode_base64, generate_salt, open_from_base64,
+    seal_to_base64, KdfParams,
+};
+use crate::Backend::DataHandler::{write_atomic_new_private, write_atomic_private, DataError};
+use crate::Backend::Sessions::unix_now;
+
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+const VAULT_AAD: &[u8] = b"sentinel-vault";
+
+/// Where the vault's master key comes from.
+#[derive(Clone)]
+pub enum MasterKey {
+    /// Derived from a passphrase with Argon2id (salt stored in the vault file).
+    Passphrase(String),
+    /// A raw 256-bit key.
+    Raw([u8; 32]),
+}
+
+impl MasterKey {
+    /// Read a passphrase from an environment variable.
+    pub fn from_env(var: &str) -> Result<Self, String> {
+        env::var(var)
+            .map(MasterKey::Passphrase)
+            .map_err(|_| format!("Environment variable '{}' is not set", var))
+    }
+
+    /// Read a base64-encoded 32-byte key from an environment variable.
+    pub fn raw_from_env(var: &str) -> Result<Self, String> {
+        let value = env::var(var).map_err(|_| format!("Environment variable '{}' is not set", var))?;
+        let key: [u8; 32] = decode_base64(&value)?
+            .try_into()
+            .map_err(|_| format!("'{}' must hold a base64-encoded 32-byte key", var))?;
+        Ok(MasterKey::Raw(key))
+    }
+
+    fn derive(&self, salt: &[u8], kdf: &KdfParams) -> Result<[u8; 32], String> {
+        match self {
+            MasterKey::Passphrase(passphrase) => derive_key_argon2id(passphrase.as_bytes(), salt, kdf),
+            MasterKey::Raw(key) => Ok(*key),
+        }
+    }
+}
+
+/// A stored secret.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Secret {
+    pub value: String,
+    /// Incremented on every rotation.
+    pub version: u32,
+    pub updated_at: u64,
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secret")
+            .field("value", &"<redacted>")
+            .field("version", &self.version)
+            .field("updated_at", &self.updated_at)
+            .finish()
+    }
+}
+
+/// On-disk layout of a vault file.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    format: u32,
+    kdf: KdfParams,
+    salt: String,
+    secrets: String,
+}
+
+/// An open, decrypted vault.
+pub struct Vault {
+    path: PathBuf,
+    key: [u8; 32],
+    kdf: KdfParams,
+    salt: Vec<u8>,
+    secrets: BTreeMap<String, Secret>,
+}
+
+impl Vault {
+    /// Create a new, empty vault file. Fails if the file already exists,
+    /// even when another process creates it concurrently.
+    pub fn create(path: impl Into<PathBuf>, master: &MasterKey) -> Result<Self, String> {
+        let path = path.into();
+        if path.exists() {
+            return Err(format!("Vault '{}' already exists", path.display()));
+        }
+
+        let kdf = KdfParams::default();
+        let salt = generate_salt().to_vec();
+        let vault = Self {
+            key: master.derive(&salt, &kdf)?,
+            path,
+            kdf,
+            salt,
+            secrets: BTreeMap::new(),
+        };
+
+        let content = vault.seal(&vault.secrets, &vault.key, &vault.salt)?;
+        write_atomic_new_private(&vault.path, content.as_bytes()).map_err(|e| match e {
+            DataError::Conflict(_) => format!("Vault '{}' already exists", vault.path.display()),
+            e => e.to_string(),
+        })?;
+        Ok(vault)
+    }
+
+    /// Open and decrypt an existing vault file.
+    pub fn open(path: impl Into<PathBuf>, master: &MasterKey) -> Result<Self, String> {
+        let path = path.into();
+        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        let file: VaultFile = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        if file.format != 1 {
+            return Err(format!("Unsupported vault format {}", file.format));
+        }
+
+        let kdf = file.kdf.bounded()?;
+        let salt = decode_base64(&file.salt)?;
+        let key = master.derive(&salt, &kdf)?;
+        let json = open_from_base64(&key, &file.secrets, VAULT_AAD)
+            .map_err(|_| "Wrong master key or corrupted vault".to_string())?;
+        let secrets = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            path,
+            key,
+            kdf,
+            salt,
+            secrets,
+        })
+    }
+
+    /// Open a vault, creating it if the file does not exist.
+    pub fn open_or_create(path: impl Into<PathBuf>, master: &MasterKey) -> Result<Self, String> {
+        let path = path.into();
+        if path.exists() {
+            Self::open(path, master)
+        } else {
+            Self::create(path, master)
+        }
+    }
+
+    /// Serialize secrets sealed under the given key and salt.
+    fn seal(&self, secrets: &BTreeMap<String, Secret>, key: &[u8; 32], salt: &[u8]) -> Result<String, String> {
+        let json = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+        let file = VaultFile {
+            format: 1,
+            kdf: self.kdf,
+            salt: encode_base64(salt),
+            secrets: seal_to_base64(key, &json, VAULT_AAD)?,
+        };
+        serde_json::to_string_pretty(&file).map_err(|e| e.to_string())
+    }
+
+    /// Atomically write secrets sealed under the given key and salt.
+    ///
+    /// The file is created owner-only (0600) and synced before the rename.
+    /// Callers update `self` only after this succeeds, so a failed write
+    /// leaves the open vault matching the file on disk.
+    fn save(&self, secrets: &BTreeMap<String, Secret>, key: &[u8; 32], salt: &[u8]) -> Result<(), String> {
+        let content = self.seal(secrets, key, salt)?;
+        Ok(write_atomic_private(&self.path, content.as_bytes())?)
+    }
+
+    /// Save a changed copy of the secrets and adopt it once written.
+    fn commit(&mut self, secrets: BTreeMap<String, Secret>) -> Result<(), String> {
+        self.save(&secrets, &self.key, &self.salt)?;
+        self.secrets = secrets;
+        Ok(())
+    }
+
+    /// Get a secret's value.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(|s| s.value.as_str())
+    }
+
+    /// Get a secret with its metadata.
+    pub fn secret(&self, name: &str) -> Option<&Secret> {
+        self.secrets.get(name)
+    }
+
+    /// List secret names (values are not returned).
+    pub fn list(&self) -> Vec<String> {
+        self.secrets.keys().cloned().collect()
+    }
+
+    /// Add a new secret. Fails if it already exists.
+    pub fn add(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if self.secrets.contains_key(name) {
+            return Err(format!("Secret '{}' already exists", name));
+        }
+
+        let mut secrets = self.secrets.clone();
+        secrets.insert(
+            name.into(),
+            Secret {
+                value: value.into(),
+                version: 1,
+                updated_at: unix_now(),
+            },
+        );
+        self.commit(secrets)
+    }
+
+    /// Replace an existing secret's value. Returns the new version.
+    pub fn rotate(&mut self, name: &str, value: &str) -> Result<u32, String> {
+        let mut secrets = self.secrets.clone();
+        let secret = secrets
+            .get_mut(name)
+            .ok_or_else(|| format!("Secret '{}' not found", name))?;
+
+        secret.value = value.into();
+        secret.version += 1;
+        secret.updated_at = unix_now();
+        let version = secret.version;
+
+        self.commit(secrets)?;
+        Ok(version)
+    }
+
+    /// Remove a secret. Returns `false` if it did not exist.
+    pub fn remove(&mut self, name: &str) -> Result<bool, String> {
+        let mut secrets = self.secrets.clone();
+        if secrets.remove(name).is_none() {
+            return Ok(false);
+        }
+
+        self.commit(secrets)?;
+        Ok(true)
+    }
+
+    /// Re-encrypt the vault under a new master key (fresh salt).
+    ///
+    /// The open vault keeps its old key if writing the file fails.
+    pub fn change_master_key(&mut self, master: &MasterKey) -> Result<(), String> {
+        let salt = generate_salt().to_vec();
+        let key = master.derive(&salt, &self.kdf)?;
+        self.save(&self.secrets, &key, &salt)?;
+
+        self.key = key;
+        self.salt = salt;
+        Ok(())
+    }
+
+    /// Run a CLI-style command, e.g. `["add", "db_password", "hunter2"]`.
+    ///
+    /// Supported: `add <name> <value>`, `rotate <name> <value>`,
+    /// `remove <name>`, `get <name>`, `list`.
+    pub fn run_command(&mut self, args: &[&str]) -> Result<String, String> {
+        match args {
+            ["add", name, value] => {
+                self.add(name, value)?;
+                Ok(format!("Added '{}'", name))
+            }
+            ["rotate", name, value] => {
+                let version = self.rotate(name, value)?;
+                Ok(format!("Rotated '{}' to version {}", name, version))
+            }
+            ["remove", name] => match self.remove(name)? {
+                true => Ok(format!("Removed '{}'", name)),
+                false => Err(format!("Secret '{}' not found", name)),
+            },
+            ["get", name] => self
+                .get(name)
+                .map(str::to_string)
+                .ok_or_else(|| format!("Secret '{}' not found", name)),
+            ["list"] => Ok(self.list().join("\n")),
+            _ => Err(
+                "Usage: add <name> <value> | rotate <name> <value> | remove <name> | get <name> | list"
+                    .into(),
+            ),
+        }
+    }
+}
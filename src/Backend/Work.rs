@@ -13,14 +13,35 @@
 //! similar to how Frontend/App.rs orchestrates the UI layer.
 
 use crate::Backend::{
-    Auth::{self, User},
+    Auth::{self, AuthError, User, LoginOutcome, TokenPair},
     Cryptography,
-    DataHandler::{CRUD, JSONHandler, YAMLHandler, XMLHandler, SQLHandler},
-    Roles::RoleViews,
+    DataHandler::{write_atomic_private_with, CRUD, DataError, DataFormat, JSONHandler, YAMLHandler, XMLHandler, SqlValue, Store},
+    Roles::{self, RoleHierarchy, RoleViews},
+    Sessions::{Session, SessionStore},
+    Users::UserRepository,
+    PasswordPolicy::{PasswordPolicy, PolicyViolation},
+    TwoFactor::TotpEnrollment,
+    Notifier::Notifier,
+    Tokens::{self, RevocationList},
+    ApiKeys::{self, ApiKey, ApiKeyStore, IssuedApiKey},
+    Vault::{MasterKey, Vault},
     TempEng::Template,
 };
 
+#[cfg(feature = "sql")]
+use crate::Backend::DataHandler::{SQLHandler, SQLTransaction};
+#[cfg(feature = "sql")]
+use crate::Backend::{
+    Sessions::{SessionConfig, SqliteSessionStore},
+    Users::SqliteUserRepository,
+};
+
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// The main backend orchestrator.
 ///
@@ -28,6 +49,8 @@ use std::collections::HashMap;
 /// load users, resolve views, and perform secure operations.
 pub struct Work {
     pub roles: RoleViews,
+    #[cfg(feature = "sql")]
+    sql: Option<Arc<SQLHandler>>,
 }
 
 impl Work {
@@ -35,9 +58,93 @@ impl Work {
     pub fn new() -> Self {
         Self {
             roles: RoleViews::new(),
+            #[cfg(feature = "sql")]
+            sql: None,
         }
     }
 
+    /// Connect to a SQL database (e.g. `sqlite://app.db?mode=rwc`).
+    #[cfg(feature = "sql")]
+    pub fn with_sql(mut self, url: &str) -> Result<Self, DataError> {
+        self.sql = Some(Arc::new(SQLHandler::connect(url)?));
+        Ok(self)
+    }
+
+    /// Keep users and sessions in the `with_sql` database, sharing its handler.
+    #[cfg(feature = "sql")]
+    pub fn with_sql_stores(self, config: SessionConfig) -> Result<Self, DataError> {
+        let sql = self
+            .sql
+            .clone()
+            .ok_or_else(|| DataError::Sql("no database configured (use Work::with_sql)".into()))?;
+
+        let users = SqliteUserRepository::with_handler(sql.clone()).map_err(DataError::Sql)?;
+        let sessions = SqliteSessionStore::with_handler(sql, config).map_err(DataError::Sql)?;
+        Auth::set_user_repository(Arc::new(users));
+        Auth::set_session_store(Arc::new(sessions));
+        Ok(self)
+    }
+
+    /// Use a custom session store (file, SQLite, ...) for all sessions.
+    pub fn with_session_store(self, store: Arc<dyn SessionStore>) -> Self {
+        Auth::set_session_store(store);
+        self
+    }
+
+    /// Choose opaque or stateless (signed / encrypted) session tokens.
+    pub fn with_session_token_mode(self, mode: Auth::SessionTokenMode) -> Self {
+        Auth::set_session_token_mode(mode);
+        self
+    }
+
+    /// Set the secret used to sign and encrypt tokens (shared across processes).
+    pub fn with_token_secret(self, secret: &[u8]) -> Self {
+        Tokens::set_token_secret(secret);
+        self
+    }
+
+    /// Use a custom revocation list for stateless session tokens.
+    pub fn with_revocation_list(self, list: Arc<dyn RevocationList>) -> Self {
+        Tokens::set_revocation_list(list);
+        self
+    }
+
+    /// Use a custom API key store.
+    pub fn with_api_key_store(self, store: Arc<dyn ApiKeyStore>) -> Self {
+        ApiKeys::set_api_key_store(store);
+        self
+    }
+
+    /// Use a custom user repository (JSON file, SQLite, ...) for all users.
+    pub fn with_user_repository(self, repo: Arc<dyn UserRepository>) -> Self {
+        Auth::set_user_repository(repo);
+        self
+    }
+
+    /// Use a custom password policy for registration and password change.
+    pub fn with_password_policy(self, policy: PasswordPolicy) -> Self {
+        Auth::set_password_policy(policy);
+        self
+    }
+
+    /// Tune failed-login throttling and lockout.
+    pub fn with_login_throttle(self, throttle: crate::Middlend::Security::LoginThrottle) -> Self {
+        Auth::set_login_throttle(throttle);
+        self
+    }
+
+    /// Deliver reset / verification messages through a custom notifier.
+    pub fn with_notifier(self, notifier: Arc<dyn Notifier>) -> Self {
+        Auth::set_notifier(notifier);
+        self
+    }
+
+    /// Use a role hierarchy for role checks and view resolution.
+    pub fn with_role_hierarchy(self, hierarchy: RoleHierarchy) -> Self {
+        Roles::set_role_hierarchy(hierarchy);
+        self
+    }
+
     /// Register a role → view mapping.
     pub fn register_role_view(
         mut self,
@@ -52,27 +159,149 @@ impl Work {
     // AUTHENTICATION OPERATIONS
     // -------------------------
 
-    /// Register a new user using chosen hashing algorithm.
+    /// Register and store a new user (Argon2id, random salt).
     pub fn register_user(
         &self,
         username: &str,
         password: &str,
-        role: &str,
-        salt: &str,
-        use_argon2: bool,
-    ) -> Result<User, String> {
-        Auth::register_user(username, password, role, salt, use_argon2)
+        roles: &[&str],
+    ) -> Result<User, AuthError> {
+        Auth::register_user(username, password, roles)
+    }
+
+    /// Check a password against the active policy.
+    pub fn check_password(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<(), Vec<PolicyViolation>> {
+        Auth::check_password(username, password)
+    }
+
+    /// Change a stored user's password and revoke their sessions.
+    pub fn change_password(
+        &self,
+        username: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<User, AuthError> {
+        Auth::change_password(username, current_password, new_password)
+    }
+
+    /// Send a password reset token to a user.
+    pub fn request_password_reset(&self, username: &str) -> Result<(), String> {
+        Auth::request_password_reset(username)
+    }
+
+    /// Set a new password using a reset token.
+    pub fn complete_password_reset(&self, token: &str, new_password: &str) -> Result<User, AuthError> {
+        Auth::complete_password_reset(token, new_password)
+    }
+
+    /// Send an account verification token to a user.
+    pub fn request_account_verification(&self, username: &str) -> Result<(), String> {
+        Auth::request_account_verification(username)
+    }
+
+    /// Mark an account as verified using a verification token.
+    pub fn verify_account(&self, token: &str) -> Result<User, String> {
+        Auth::verify_account(token)
+    }
+
+    /// Look up a registered user by username.
+    pub fn find_user(&self, username: &str) -> Result<Option<User>, String> {
+        Auth::find_user(username)
+    }
+
+    /// Save changes to an existing user.
+    pub fn update_user(&self, user: &User) -> Result<(), String> {
+        Auth::user_repository().update(user)
+    }
+
+    /// Delete a user and revoke their sessions and API keys.
+    ///
+    /// Lookup is case-insensitive; revocation uses the stored username,
+    /// which sessions and keys are recorded under.
+    pub fn delete_user(&self, username: &str) -> Result<bool, String> {
+        let repo = Auth::user_repository();
+        let Some(user) = repo.find_by_username(username)? else {
+            return Ok(false);
+        };
+
+        let removed = repo.delete(&user.username)?;
+        if removed {
+            Auth::revoke_user_sessions(&user.username);
+            Auth::revoke_user_api_keys(&user.username);
+        }
+        Ok(removed)
     }
 
-    /// Attempt login and return session token.
+    /// List every registered user.
+    pub fn list_users(&self) -> Result<Vec<User>, String> {
+        Auth::user_repository().list()
+    }
+
+    /// Attempt login by username (first step when TOTP is enabled).
+    ///
+    /// Unknown usernames count towards the lockout like wrong passwords.
+    pub fn login(&self, username: &str, password: &str, client_id: &str) -> Result<LoginOutcome, String> {
+        Auth::login(username, password, client_id)
+    }
+
+    /// Attempt login for an already loaded user.
+    ///
+    /// If `needs_save` is set on the outcome, save `user` afterwards.
     pub fn login_user(
         &self,
-        user: &User,
+        user: &mut User,
         password: &str,
-        salt: &str,
-        use_argon2: bool,
-    ) -> Option<String> {
-        Auth::login_user(user, password, salt, use_argon2)
+        client_id: &str,
+    ) -> Result<LoginOutcome, String> {
+        Auth::login_user(user, password, client_id)
+    }
+
+    /// Finish a two-step login with a TOTP or recovery code.
+    pub fn complete_mfa_login(
+        &self,
+        user: &mut User,
+        challenge: &str,
+        code: &str,
+    ) -> Result<LoginOutcome, String> {
+        Auth::complete_mfa_login(user, challenge, code)
+    }
+
+    /// Start TOTP enrollment for a user.
+    pub fn begin_totp_enrollment(&self, user: &mut User, issuer: &str) -> TotpEnrollment {
+        Auth::begin_totp_enrollment(user, issuer)
+    }
+
+    /// Confirm TOTP enrollment and return recovery codes.
+    pub fn confirm_totp_enrollment(
+        &self,
+        user: &mut User,
+        code: &str,
+    ) -> Result<Vec<String>, String> {
+        Auth::confirm_totp_enrollment(user, code)
+    }
+
+    /// Turn off TOTP for a user.
+    pub fn disable_totp(&self, user: &mut User) {
+        Auth::disable_totp(user)
+    }
+
+    /// Lift a username lockout.
+    pub fn unlock_user(&self, username: &str) -> bool {
+        Auth::unlock_user(username)
+    }
+
+    /// Lift a client lockout.
+    pub fn unlock_client(&self, client_id: &str) -> bool {
+        Auth::unlock_client(client_id)
+    }
+
+    /// List active login lockouts.
+    pub fn active_lockouts(&self) -> Vec<(String, std::time::Duration)> {
+        Auth::active_lockouts()
     }
 
     /// Validate a session token.
@@ -80,73 +309,227 @@ impl Work {
         Auth::validate_session(token)
     }
 
-    /// Check if a user has a required role.
+    /// End a single session.
+    pub fn logout(&self, token: &str) -> bool {
+        Auth::logout(token)
+    }
+
+    /// Exchange a refresh token for a new session + refresh token (rotation).
+    pub fn refresh_session(&self, refresh_token: &str) -> Result<TokenPair, String> {
+        Auth::refresh_session(refresh_token)
+    }
+
+    /// Revoke a refresh token and its whole rotation family.
+    pub fn revoke_refresh_token(&self, refresh_token: &str) -> bool {
+        Auth::revoke_refresh_token(refresh_token)
+    }
+
+    /// Revoke every session and refresh token of a user.
+    pub fn revoke_user_sessions(&self, username: &str) -> usize {
+        Auth::revoke_user_sessions(username)
+    }
+
+    /// List the live sessions of a user (`token` holds the session id, not the token).
+    pub fn list_sessions(&self, username: &str) -> Vec<Session> {
+        Auth::list_sessions(username)
+    }
+
+    /// Drop expired sessions and refresh tokens now (also done when sessions start).
+    pub fn purge_expired_sessions(&self) -> usize {
+        Auth::purge_expired_sessions()
+    }
+
+    /// Revoke a session by the id shown in `list_sessions`.
+    pub fn revoke_session_id(&self, id: &str) -> bool {
+        Auth::revoke_session_id(id)
+    }
+
+    /// Check if a user has a required role (hierarchy-aware).
     pub fn user_has_role(&self, user: &User, role: &str) -> bool {
         Auth::user_has_role(user, role)
     }
 
+    /// Check if a user's roles grant a permission.
+    pub fn user_can(&self, user: &User, permission: &str) -> bool {
+        Auth::user_can(user, permission)
+    }
+
+    // -------------------------
+    // SERVICE ACCOUNTS + API KEYS
+    // -------------------------
+
+    /// Register a service account (API keys only, no password).
+    pub fn register_service_account(&self, username: &str, roles: &[&str]) -> Result<User, String> {
+        Auth::register_service_account(username, roles)
+    }
+
+    /// Issue an API key for a service account. The returned `key` is shown once.
+    pub fn issue_api_key(
+        &self,
+        username: &str,
+        scopes: &[&str],
+        ttl: Option<Duration>,
+    ) -> Result<IssuedApiKey, String> {
+        Auth::issue_api_key(username, scopes, ttl)
+    }
+
+    /// Replace an API key with a fresh one (same owner, scopes and lifetime).
+    pub fn rotate_api_key(&self, id: &str) -> Result<IssuedApiKey, String> {
+        ApiKeys::rotate_api_key(id)
+    }
+
+    /// Revoke an API key.
+    pub fn revoke_api_key(&self, id: &str) -> bool {
+        ApiKeys::revoke_api_key(id)
+    }
+
+    /// List a service account's API keys.
+    pub fn list_api_keys(&self, username: &str) -> Vec<ApiKey> {
+        ApiKeys::list_api_keys(username)
+    }
+
     // -------------------------
     // DATA HANDLING OPERATIONS
     // -------------------------
 
-    pub fn save_json<T>(&self, data: &T) -> Result<String, String>
+    pub fn save_json<T>(&self, data: &T) -> Result<String, DataError>
     where
         T: serde::Serialize + for<'de> serde::Deserialize<'de>,
     {
         JSONHandler.create(data)
     }
 
-    pub fn load_json<T>(&self, source: &str) -> Result<T, String>
+    pub fn load_json<T>(&self, source: &str) -> Result<T, DataError>
     where
         T: serde::Serialize + for<'de> serde::Deserialize<'de>,
     {
         JSONHandler.read(source)
     }
 
-    pub fn save_yaml<T>(&self, data: &T) -> Result<String, String>
+    pub fn save_yaml<T>(&self, data: &T) -> Result<String, DataError>
     where
         T: serde::Serialize + for<'de> serde::Deserialize<'de>,
     {
         YAMLHandler.create(data)
     }
 
-    pub fn load_yaml<T>(&self, source: &str) -> Result<T, String>
+    pub fn load_yaml<T>(&self, source: &str) -> Result<T, DataError>
     where
         T: serde::Serialize + for<'de> serde::Deserialize<'de>,
     {
         YAMLHandler.read(source)
     }
 
-    pub fn save_xml<T>(&self, data: &T) -> Result<String, String>
+    pub fn save_xml<T>(&self, data: &T) -> Result<String, DataError>
     where
         T: serde::Serialize + for<'de> serde::Deserialize<'de>,
     {
         XMLHandler.create(data)
     }
 
-    pub fn load_xml<T>(&self, source: &str) -> Result<T, String>
+    pub fn load_xml<T>(&self, source: &str) -> Result<T, DataError>
     where
         T: serde::Serialize + for<'de> serde::Deserialize<'de>,
     {
         XMLHandler.read(source)
     }
 
-    pub fn sql_query(&self, query: &str) -> Result<String, String> {
-        SQLHandler.query(query)
+    /// Open a store keeping one file per record in `dir`.
+    pub fn open_store<T>(&self, dir: &str, format: DataFormat) -> Result<Store<T>, DataError>
+    where
+        T: serde::Serialize + for<'de> serde::Deserialize<'de> + Clone,
+    {
+        Store::directory(dir, format)
+    }
+
+    /// Open a store keeping every record in a single file.
+    pub fn open_store_file<T>(&self, path: &str, format: DataFormat) -> Result<Store<T>, DataError>
+    where
+        T: serde::Serialize + for<'de> serde::Deserialize<'de> + Clone,
+    {
+        Store::file(path, format)
+    }
+
+    /// The configured SQL handler (see `with_sql`).
+    #[cfg(feature = "sql")]
+    pub fn sql(&self) -> Result<&SQLHandler, DataError> {
+        self.sql
+            .as_deref()
+            .ok_or_else(|| DataError::Sql("no database configured (use Work::with_sql)".into()))
+    }
+
+    /// Run a parameterized query and decode every row into `T`.
+    #[cfg(feature = "sql")]
+    pub fn sql_query<T>(&self, query: &str, params: &[SqlValue]) -> Result<Vec<T>, DataError>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        self.sql()?.fetch_all(query, params)
+    }
+
+    /// Run a parameterized statement, returning the number of affected rows.
+    #[cfg(feature = "sql")]
+    pub fn sql_execute(&self, query: &str, params: &[SqlValue]) -> Result<u64, DataError> {
+        self.sql()?.execute(query, params)
+    }
+
+    /// Run statements in a transaction (committed only if `work` returns `Ok`).
+    #[cfg(feature = "sql")]
+    pub fn sql_transaction<R>(
+        &self,
+        work: impl FnOnce(&mut SQLTransaction<'_>) -> Result<R, DataError>,
+    ) -> Result<R, DataError> {
+        self.sql()?.transaction(work)
+    }
+
+    #[cfg(not(feature = "sql"))]
+    pub fn sql_query<T>(&self, _query: &str, _params: &[SqlValue]) -> Result<Vec<T>, DataError>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        Err(DataError::Sql("SQL support requires the `sql` feature".into()))
+    }
+
+    #[cfg(not(feature = "sql"))]
+    pub fn sql_execute(&self, _query: &str, _params: &[SqlValue]) -> Result<u64, DataError> {
+        Err(DataError::Sql("SQL support requires the `sql` feature".into()))
     }
 
     // -------------------------
     // CRYPTOGRAPHY OPERATIONS
     // -------------------------
 
-    pub fn hash_argon2id(&self, password: &str, salt: &str) -> Result<String, String> {
-        Cryptography::hash_argon2id(password, salt)
+    pub fn hash_argon2id(&self, password: &str) -> Result<String, String> {
+        Cryptography::hash_argon2id(password)
+    }
+
+    pub fn hash_argon2id_with_salt(&self, password: &str, salt: &str) -> Result<String, String> {
+        Cryptography::hash_argon2id_with_salt(password, salt)
+    }
+
+    /// Tune Argon2id cost parameters and pepper for this deployment.
+    pub fn with_argon2_config(self, config: Cryptography::Argon2Config) -> Self {
+        Cryptography::set_argon2_config(config);
+        self
     }
 
     pub fn hash_sha256(&self, input: &str) -> String {
         Cryptography::hash_sha256(input)
     }
 
+    pub fn sign_ed25519(&self, key: &Cryptography::SigningKey, data: &[u8]) -> Vec<u8> {
+        Cryptography::sign_ed25519(key, data)
+    }
+
+    pub fn verify_ed25519(
+        &self,
+        key: &Cryptography::VerifyingKey,
+        data: &[u8],
+        signature: &[u8],
+    ) -> bool {
+        Cryptography::verify_ed25519(key, data, signature)
+    }
+
     pub fn encrypt_aes256(
         &self,
         key: &[u8; 32],
@@ -165,6 +548,61 @@ impl Work {
         Cryptography::decrypt_aes256(key, nonce, ciphertext)
     }
 
+    pub fn seal(&self, key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        Cryptography::seal(key, plaintext, aad)
+    }
+
+    pub fn open(&self, key: &[u8; 32], envelope: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        Cryptography::open(key, envelope, aad)
+    }
+
+    pub fn derive_key_argon2id(
+        &self,
+        passphrase: &str,
+        salt: &[u8],
+    ) -> Result<[u8; 32], String> {
+        Cryptography::derive_key_argon2id(passphrase.as_bytes(), salt, &Cryptography::KdfParams::default())
+    }
+
+    pub fn derive_key_hkdf(&self, ikm: &[u8], salt: &[u8], info: &[u8]) -> Result<[u8; 32], String> {
+        Cryptography::derive_key_hkdf(ikm, salt, info)
+    }
+
+    /// Encrypt a file of any size in authenticated chunks.
+    ///
+    /// `output` is only replaced once the whole file is encrypted.
+    pub fn encrypt_file(&self, key: &[u8; 32], input: &str, output: &str) -> Result<u64, String> {
+        let reader = BufReader::new(File::open(input).map_err(|e| e.to_string())?);
+        write_atomic_private_with(Path::new(output), |file| {
+            Cryptography::encrypt_stream(key, reader, BufWriter::new(file))
+        })
+    }
+
+    /// Decrypt a file produced by `encrypt_file`.
+    ///
+    /// Output goes to a private (0600) temp file that is only moved into
+    /// place once every chunk has been authenticated.
+    pub fn decrypt_file(&self, key: &[u8; 32], input: &str, output: &str) -> Result<u64, String> {
+        let reader = BufReader::new(File::open(input).map_err(|e| e.to_string())?);
+        write_atomic_private_with(Path::new(output), |file| {
+            Cryptography::decrypt_stream(key, reader, BufWriter::new(file))
+        })
+    }
+
+    // -------------------------
+    // SECRETS VAULT
+    // -------------------------
+
+    /// Open an existing encrypted secrets vault.
+    pub fn open_vault(&self, path: &str, master: &MasterKey) -> Result<Vault, String> {
+        Vault::open(path, master)
+    }
+
+    /// Create a new, empty secrets vault. Fails if the file already exists.
+    pub fn create_vault(&self, path: &str, master: &MasterKey) -> Result<Vault, String> {
+        Vault::create(path, master)
+    }
+
     // -------------------------
     // TEMPLATE ENGINE OPERATIONS
     // -------------------------
@@ -7,6 +7,14 @@
 
 pub mod Auth;
 pub mod DataHandler;
+pub mod Sessions;
+pub mod Users;
+pub mod PasswordPolicy;
+pub mod TwoFactor;
+pub mod Tokens;
+pub mod Notifier;
+pub mod ApiKeys;
+pub mod Vault;
 
 // Re‑exports for cleaner API
 pub use Auth::*;
@@ -7,8 +7,9 @@
 //! - File integrity
 //! - Template integrity
 //! - API integrity
-//! - Session validity
+//! - Session + API key validity
 //! - Role validity
+//! - Permission validity
 
 use crate::Middlend::Security::*;
 use crate::Backend::Auth::User;
@@ -22,11 +23,21 @@ impl Checker {
         enforce_session(token)
     }
 
+    /// Check if an API key is valid.
+    pub fn check_api_key(key: &str) -> Result<(), String> {
+        enforce_api_key(key).map(|_| ())
+    }
+
     /// Check if a user has a required role.
     pub fn check_role(user: &User, role: &str) -> Result<(), String> {
         enforce_role(user, role)
     }
 
+    /// Check if a user has a required permission.
+    pub fn check_permission(user: &User, permission: &str) -> Result<(), String> {
+        enforce_permission(user, permission)
+    }
+
     /// Check if input is safe.
     pub fn check_input(input: &str) -> Result<String, String> {
         validate_input(input)
@@ -8,10 +8,15 @@
 //! - APIs/
 //! - Work/
 //!
-//! Also loads templates and page modules.
+//! Also loads templates, page modules, the role hierarchy, policies,
+//! passphrase-protected keyrings and the secrets vault.
 
 use crate::Middlend::NeededStruct::ProjectStructure;
 use crate::Backend::TempEng::Template;
+use crate::Backend::Roles::RoleHierarchy;
+use crate::Backend::Cryptography::Keyring;
+use crate::Backend::Vault::{MasterKey, Vault};
+use crate::Middlend::Policy::PolicySet;
 use std::fs;
 
 pub struct Loader {
@@ -51,4 +56,35 @@ impl Loader {
         let path = self.structure.describe.path.join(name);
         fs::read_to_string(path).map_err(|e| e.to_string())
     }
+
+    /// Load the role hierarchy from Describe/roles.yaml.
+    pub fn load_roles(&self) -> Result<RoleHierarchy, String> {
+        let yaml = self.load_config("roles.yaml")?;
+        RoleHierarchy::from_yaml(&yaml)
+    }
+
+    /// Load policy rules from Describe/policies.yaml.
+    pub fn load_policies(&self) -> Result<PolicySet, String> {
+        let yaml = self.load_config("policies.yaml")?;
+        PolicySet::from_yaml(&yaml)
+    }
+
+    /// Load a passphrase-protected keyring from Describe/ (e.g. `keyring.json`).
+    pub fn load_keyring(&self, name: &str, passphrase: &str) -> Result<Keyring, String> {
+        let content = self.load_config(name)?;
+        Keyring::from_protected(&content, passphrase)
+    }
+
+    /// Open the encrypted secrets vault in Describe/ (e.g. `vault.json`).
+    pub fn load_vault(&self, name: &str, master: &MasterKey) -> Result<Vault, String> {
+        Vault::open(self.structure.describe.path.join(name), master)
+    }
+
+    /// Read a single secret from the vault in Describe/.
+    pub fn load_secret(&self, vault: &str, master: &MasterKey, name: &str) -> Result<String, String> {
+        self.load_vault(vault, master)?
+            .get(name)
+            .map(str::to_string)
+            .ok_or_else(|| format!("Secret '{}' not found", name))
+    }
 }
@@ -5,25 +5,80 @@
 //! Provides a pipeline for:
 //! - Security policy enforcement
 //! - Input validation
-//! - Session checks
+//! - Session / API key checks
 //! - Role checks
+//! - Permission checks
+//! - Attribute-based policy rules
 //! - Rate limiting
 //!
 //! This is used by Backend routing and API handlers.
 
 use crate::Middlend::Security::*;
+use crate::Middlend::Policy::{AccessRequest, PolicyRule, PolicySet};
 use crate::Backend::Auth::User;
+use std::collections::HashMap;
 
 pub struct MiddlewareContext<'a> {
     pub session_token: Option<&'a str>,
+    /// Alternative to `session_token` for service accounts.
+    pub api_key: Option<&'a str>,
     pub user: Option<&'a User>,
     pub input: Option<&'a str>,
     pub client_id: &'a str,
+    pub action: Option<&'a str>,
+    pub resource: Option<&'a HashMap<String, String>>,
+}
+
+impl<'a> MiddlewareContext<'a> {
+    /// Start a context for a client; everything else is unset.
+    pub fn new(client_id: &'a str) -> Self {
+        Self {
+            session_token: None,
+            api_key: None,
+            user: None,
+            input: None,
+            client_id,
+            action: None,
+            resource: None,
+        }
+    }
+
+    /// Authenticate with a session token.
+    pub fn with_session(mut self, token: &'a str) -> Self {
+        self.session_token = Some(token);
+        self
+    }
+
+    /// Authenticate with an API key (service accounts).
+    pub fn with_api_key(mut self, key: &'a str) -> Self {
+        self.api_key = Some(key);
+        self
+    }
+
+    /// The user making the request.
+    pub fn with_user(mut self, user: &'a User) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Input to validate and sanitize.
+    pub fn with_input(mut self, input: &'a str) -> Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// Set the action (and resource attributes) checked against policies.
+    pub fn with_action(mut self, action: &'a str, resource: &'a HashMap<String, String>) -> Self {
+        self.action = Some(action);
+        self.resource = Some(resource);
+        self
+    }
 }
 
 pub struct Middleware {
     pub policy: SecurityPolicy,
     pub rate_limiter: Option<RateLimiter>,
+    pub policies: PolicySet,
 }
 
 impl Middleware {
@@ -35,9 +90,22 @@ impl Middleware {
         Self {
             policy,
             rate_limiter,
+            policies: PolicySet::new(),
         }
     }
 
+    /// Register a policy rule.
+    pub fn with_rule(mut self, rule: impl PolicyRule + 'static) -> Self {
+        self.policies.add_rule(rule);
+        self
+    }
+
+    /// Register a set of policy rules (e.g. from Describe/policies.yaml).
+    pub fn with_policies(mut self, policies: PolicySet) -> Self {
+        self.policies.extend(policies);
+        self
+    }
+
     /// Run the middleware pipeline.
     pub fn run(&mut self, ctx: &MiddlewareContext) -> Result<(), String> {
         // Rate limiting
@@ -47,24 +115,71 @@ impl Middleware {
             }
         }
 
-        // Session enforcement
+        // API key authentication
+        let api_key = match ctx.api_key {
+            Some(key) => Some(enforce_api_key(key)?),
+            None => None,
+        };
+
+        // The key's service account is the principal for every check below
+        let user = match &api_key {
+            Some((owner, _)) => {
+                if let Some(user) = ctx.user {
+                    if user.username.trim().to_lowercase() != owner.username.to_lowercase() {
+                        return Err("User does not match the API key owner".into());
+                    }
+                }
+                Some(owner)
+            }
+            None => ctx.user,
+        };
+
+        // Session enforcement (a valid API key also satisfies it)
         if self.policy.require_session {
             if let Some(token) = ctx.session_token {
                 enforce_session(token)?;
-            } else {
-                return Err("Session token required".into());
+            } else if api_key.is_none() {
+                return Err("Session token or API key required".into());
             }
         }
 
         // Role enforcement
         if let Some(required_role) = &self.policy.require_role {
-            if let Some(user) = ctx.user {
+            if let Some(user) = user {
                 enforce_role(user, required_role)?;
             } else {
                 return Err("User required for role enforcement".into());
             }
         }
 
+        // Permission enforcement (API keys must also carry the scope)
+        if let Some(permission) = &self.policy.require_permission {
+            if let Some((_, key)) = &api_key {
+                enforce_scope(key, permission)?;
+            }
+
+            if let Some(user) = user {
+                enforce_permission(user, permission)?;
+            } else {
+                return Err("User required for permission enforcement".into());
+            }
+        }
+
+        // Policy rules
+        if !self.policies.is_empty() {
+            let action = ctx
+                .action
+                .ok_or_else(|| "Action required for policy enforcement".to_string())?;
+            let empty = HashMap::new();
+            let request = AccessRequest::new(user, action, ctx.resource.unwrap_or(&empty))
+                .with_env("client_id", ctx.client_id);
+
+            let decision = self.policies.evaluate(&request);
+            if !decision.is_allowed() {
+                return Err(format!("Access denied: {}", decision.reason));
+            }
+        }
+
         // Input sanitization
         if self.policy.sanitize_inputs {
             if let Some(input) = ctx.input {
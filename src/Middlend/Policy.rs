@@ -0,0 +1,495 @@
+// Sentinel/src/Middlend/Policy.rs
+
+//! Sentinel Policy Engine
+//!
+//! Provides:
+//! - Attribute-based access rules (`PolicyRule`)
+//! - Access requests built from user, action, resource attributes + environment
+//! - A small YAML rule language loaded from Describe/policies.yaml
+//! - Deny-overrides evaluation with a reason for every decision
+//!
+//! Example `Describe/policies.yaml`:
+//!
+//! ```yaml
+//! policies:
+//!   - name: owners-edit-documents
+//!     effect: allow
+//!     actions: [documents.edit]
+//!     when:
+//!       - resource.owner == user.username
+//!   - name: after-hours
+//!     effect: deny
+//!     actions: ["*"]
+//!     when:
+//!       - env.hour >= 18
+//! ```
+
+use crate::Backend::Auth::User;
+use crate::Backend::DataHandler::{CRUD, YAMLHandler};
+use crate::Backend::Roles::permission_matches;
+use crate::Backend::Sessions::unix_now;
+
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+/// Outcome of a rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+/// A decision plus the reason it was made (for auditing).
+#[derive(Clone, Debug)]
+pub struct Decision {
+    pub effect: Effect,
+    pub reason: String,
+}
+
+impl Decision {
+    pub fn allow(reason: impl Into<String>) -> Self {
+        Self { effect: Effect::Allow, reason: reason.into() }
+    }
+
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self { effect: Effect::Deny, reason: reason.into() }
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        self.effect == Effect::Allow
+    }
+}
+
+/// Everything a rule may look at.
+pub struct AccessRequest<'a> {
+    pub user: Option<&'a User>,
+    pub action: &'a str,
+    pub resource: &'a HashMap<String, String>,
+    pub env: HashMap<String, String>,
+}
+
+impl<'a> AccessRequest<'a> {
+    /// Build a request with the default environment (`time`, `hour`, `weekday`).
+    pub fn new(
+        user: Option<&'a User>,
+        action: &'a str,
+        resource: &'a HashMap<String, String>,
+    ) -> Self {
+        Self {
+            user,
+            action,
+            resource,
+            env: default_env(),
+        }
+    }
+
+    /// Add an environment attribute (e.g. `client_id`).
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Current time attributes (UTC): `time`, `hour` (0-23), `weekday` (0 = Sunday).
+pub fn default_env() -> HashMap<String, String> {
+    let now = unix_now();
+    let mut env = HashMap::new();
+
+    env.insert("time".into(), now.to_string());
+    env.insert("hour".into(), ((now / 3600) % 24).to_string());
+    env.insert("weekday".into(), ((now / 86_400 + 4) % 7).to_string());
+    env
+}
+
+/// An authorization rule.
+///
+/// Return `None` when the rule does not apply to the request.
+pub trait PolicyRule: Send + Sync {
+    fn name(&self) -> &str;
+    fn evaluate(&self, request: &AccessRequest) -> Option<Decision>;
+}
+
+/// ------------------------------
+/// CONDITIONS
+/// ------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+    Attribute(String),
+    Literal(Value),
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    fn number(&self) -> Option<f64> {
+        match self {
+            Value::Text(text) => text.parse().ok(),
+            Value::List(_) => None,
+        }
+    }
+}
+
+/// A parsed `<left> <op> <right>` condition.
+#[derive(Clone, Debug)]
+pub struct Condition {
+    source: String,
+    left: Operand,
+    op: Operator,
+    right: Operand,
+}
+
+impl Condition {
+    /// Parse a condition such as `resource.owner == user.username`.
+    ///
+    /// Splits on the earliest operator outside quotes, so quoted
+    /// literals may contain operator characters.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        const OPERATORS: [(&str, Operator); 7] = [
+            ("==", Operator::Eq),
+            ("!=", Operator::Ne),
+            ("<=", Operator::Le),
+            (">=", Operator::Ge),
+            ("<", Operator::Lt),
+            (">", Operator::Gt),
+            (" in ", Operator::In),
+        ];
+
+        let mut quote = None;
+        for (i, c) in source.char_indices() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '\'' || c == '"' => quote = Some(c),
+                None => {
+                    let rest = &source[i..];
+                    if let Some((token, op)) = OPERATORS.iter().find(|(t, _)| rest.starts_with(t)) {
+                        return Ok(Self {
+                            source: source.trim().to_string(),
+                            left: parse_operand(&source[..i])?,
+                            op: *op,
+                            right: parse_operand(&rest[token.len()..])?,
+                        });
+                    }
+                }
+            }
+        }
+
+        Err(format!("Invalid policy condition '{}'", source))
+    }
+
+    /// Evaluate the condition (missing attributes make it false).
+    pub fn holds(&self, request: &AccessRequest) -> bool {
+        let (left, right) = match (resolve(&self.left, request), resolve(&self.right, request)) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return false,
+        };
+
+        match self.op {
+            Operator::Eq => values_equal(&left, &right),
+            Operator::Ne => !values_equal(&left, &right),
+            Operator::In => match (&left, &right) {
+                (Value::Text(item), Value::List(list)) => list.contains(item),
+                _ => false,
+            },
+            Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge => {
+                match (left.number(), right.number()) {
+                    (Some(a), Some(b)) => match self.op {
+                        Operator::Lt => a < b,
+                        Operator::Le => a <= b,
+                        Operator::Gt => a > b,
+                        _ => a >= b,
+                    },
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+fn parse_operand(raw: &str) -> Result<Operand, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Err("Empty operand in policy condition".into());
+    }
+
+    if let Some(inner) = raw.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+        let items = inner
+            .split(',')
+            .map(|item| unquote(item.trim()).to_string())
+            .filter(|item| !item.is_empty())
+            .collect();
+        return Ok(Operand::Literal(Value::List(items)));
+    }
+
+    let quoted = (raw.starts_with('\'') && raw.ends_with('\''))
+        || (raw.starts_with('"') && raw.ends_with('"'));
+    if quoted && raw.len() >= 2 {
+        return Ok(Operand::Literal(Value::Text(unquote(raw).to_string())));
+    }
+
+    if raw == "action" || raw.starts_with("user.") || raw.starts_with("resource.") || raw.starts_with("env.") {
+        Ok(Operand::Attribute(raw.to_string()))
+    } else {
+        Ok(Operand::Literal(Value::Text(raw.to_string())))
+    }
+}
+
+fn unquote(raw: &str) -> &str {
+    raw.trim_matches(|c| c == '\'' || c == '"')
+}
+
+fn resolve(operand: &Operand, request: &AccessRequest) -> Option<Value> {
+    let path = match operand {
+        Operand::Literal(value) => return Some(value.clone()),
+        Operand::Attribute(path) => path.as_str(),
+    };
+
+    if path == "action" {
+        return Some(Value::Text(request.action.to_string()));
+    }
+    if let Some(key) = path.strip_prefix("resource.") {
+        return request.resource.get(key).cloned().map(Value::Text);
+    }
+    if let Some(key) = path.strip_prefix("env.") {
+        return request.env.get(key).cloned().map(Value::Text);
+    }
+
+    let user = request.user?;
+    match path.strip_prefix("user.")? {
+        "username" => Some(Value::Text(user.username.clone())),
+        "roles" => Some(Value::List(user.roles.clone())),
+        "email" => user.email.clone().map(Value::Text),
+        "verified" => Some(Value::Text(user.verified.to_string())),
+        _ => None,
+    }
+}
+
+/// Exact text comparison; numbers are only parsed for `<`, `<=`, `>`, `>=`.
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Text(a), Value::Text(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// ------------------------------
+/// YAML RULES
+/// ------------------------------
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PolicyStatementDef {
+    name: String,
+    effect: Effect,
+    #[serde(default)]
+    actions: Vec<String>,
+    #[serde(default)]
+    when: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PoliciesFile {
+    #[serde(default)]
+    policies: Vec<PolicyStatementDef>,
+}
+
+/// A declarative rule: applies to matching actions when all conditions hold.
+#[derive(Clone, Debug)]
+pub struct PolicyStatement {
+    pub name: String,
+    pub effect: Effect,
+    pub actions: Vec<String>,
+    pub conditions: Vec<Condition>,
+}
+
+impl PolicyStatement {
+    /// Create a statement (`actions` support `*` and `prefix.*`).
+    pub fn new(name: &str, effect: Effect, actions: &[&str], conditions: &[&str]) -> Result<Self, String> {
+        Ok(Self {
+            name: name.into(),
+            effect,
+            actions: actions.iter().map(|a| a.to_string()).collect(),
+            conditions: conditions
+                .iter()
+                .map(|c| Condition::parse(c))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl PolicyRule for PolicyStatement {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn evaluate(&self, request: &AccessRequest) -> Option<Decision> {
+        let action_matches = self.actions.is_empty()
+            || self.actions.iter().any(|a| permission_matches(a, request.action));
+        if !action_matches || !self.conditions.iter().all(|c| c.holds(request)) {
+            return None;
+        }
+
+        let reason = if self.conditions.is_empty() {
+            format!("policy '{}' matched action '{}'", self.name, request.action)
+        } else {
+            let conditions: Vec<&str> = self.conditions.iter().map(|c| c.source.as_str()).collect();
+            format!("policy '{}' matched: {}", self.name, conditions.join(" and "))
+        };
+
+        Some(Decision { effect: self.effect, reason })
+    }
+}
+
+/// ------------------------------
+/// POLICY SET
+/// ------------------------------
+
+/// A set of rules combined with deny-overrides (no matching allow = deny).
+#[derive(Default)]
+pub struct PolicySet {
+    rules: Vec<Box<dyn PolicyRule>>,
+}
+
+impl PolicySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load rules from policies YAML.
+    pub fn from_yaml(yaml: &str) -> Result<Self, String> {
+        let file: PoliciesFile = YAMLHandler.read(yaml)?;
+        let mut set = Self::new();
+
+        for def in file.policies {
+            let actions: Vec<&str> = def.actions.iter().map(String::as_str).collect();
+            let conditions: Vec<&str> = def.when.iter().map(String::as_str).collect();
+            set.add_rule(PolicyStatement::new(&def.name, def.effect, &actions, &conditions)?);
+        }
+
+        Ok(set)
+    }
+
+    /// Register a rule.
+    pub fn add_rule(&mut self, rule: impl PolicyRule + 'static) {
+        self.rules.push(Box::new(rule));
+    }
+
+    /// Append every rule from another set.
+    pub fn extend(&mut self, other: PolicySet) {
+        self.rules.extend(other.rules);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluate all rules and log the decision.
+    pub fn evaluate(&self, request: &AccessRequest) -> Decision {
+        let mut allow = None;
+        let mut deny = None;
+
+        for rule in &self.rules {
+            match rule.evaluate(request) {
+                Some(d) if d.effect == Effect::Deny => {
+                    deny = Some(d);
+                    break;
+                }
+                Some(d) => {
+                    allow.get_or_insert(d);
+                }
+                None => {}
+            }
+        }
+
+        let decision = deny.or(allow).unwrap_or_else(|| {
+            Decision::deny(format!("no policy allows action '{}'", request.action))
+        });
+
+        let subject = request.user.map(|u| u.username.as_str()).unwrap_or("<anonymous>");
+        match decision.effect {
+            Effect::Allow => log::info!("policy allow: {} -> {} ({})", subject, request.action, decision.reason),
+            Effect::Deny => log::warn!("policy deny: {} -> {} ({})", subject, request.action, decision.reason),
+        }
+
+        decision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str) -> User {
+        User {
+            username: name.into(),
+            ..User::default()
+        }
+    }
+
+    #[test]
+    fn equality_compares_text_exactly() {
+        let condition = Condition::parse("resource.owner == user.username").unwrap();
+        let resource = HashMap::from([("owner".to_string(), "1".to_string())]);
+
+        for name in ["1.0", "01", "+1"] {
+            let u = user(name);
+            assert!(!condition.holds(&AccessRequest::new(Some(&u), "documents.edit", &resource)));
+        }
+        let owner = user("1");
+        assert!(condition.holds(&AccessRequest::new(Some(&owner), "documents.edit", &resource)));
+
+        let inf = HashMap::from([("a".to_string(), "inf".to_string())]);
+        let request = AccessRequest::new(None, "x", &inf);
+        assert!(!Condition::parse("resource.a == Infinity").unwrap().holds(&request));
+        assert!(Condition::parse("resource.a != Infinity").unwrap().holds(&request));
+    }
+
+    #[test]
+    fn ordering_still_compares_numbers() {
+        let resource = HashMap::from([("size".to_string(), "10".to_string())]);
+        let request = AccessRequest::new(None, "x", &resource);
+
+        assert!(Condition::parse("resource.size > 9.5").unwrap().holds(&request));
+        assert!(Condition::parse("resource.size <= 010").unwrap().holds(&request));
+        assert!(!Condition::parse("resource.size < 2").unwrap().holds(&request));
+    }
+
+    #[test]
+    fn operators_inside_quotes_are_ignored() {
+        let condition = Condition::parse(r#"resource.note != "a==b""#).unwrap();
+        assert_eq!(condition.op, Operator::Ne);
+
+        let same = HashMap::from([("note".to_string(), "a==b".to_string())]);
+        let other = HashMap::from([("note".to_string(), "a".to_string())]);
+        assert!(!condition.holds(&AccessRequest::new(None, "x", &same)));
+        assert!(condition.holds(&AccessRequest::new(None, "x", &other)));
+
+        let condition = Condition::parse("resource.tag == 'x < y'").unwrap();
+        assert_eq!(condition.op, Operator::Eq);
+        let tagged = HashMap::from([("tag".to_string(), "x < y".to_string())]);
+        assert!(condition.holds(&AccessRequest::new(None, "x", &tagged)));
+    }
+
+    #[test]
+    fn earliest_operator_wins() {
+        let condition = Condition::parse("resource.a < resource.b == c").unwrap();
+        assert_eq!(condition.op, Operator::Lt);
+        assert!(Condition::parse("resource.a").is_err());
+    }
+}
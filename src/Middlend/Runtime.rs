@@ -5,7 +5,7 @@
 //! The central execution engine that:
 //! - Loads project structure
 //! - Validates folders + templates
-//! - Loads identity metadata
+//! - Loads identity metadata + role hierarchy + policies
 //! - Runs middleware
 //! - Executes extensions
 //! - Connects Backend + Frontend
@@ -18,10 +18,14 @@ use crate::Middlend::{
     Validator::Validator,
     Identity::Identity,
     Middleware::{Middleware, MiddlewareContext},
+    Policy::PolicySet,
     Extensions::{Extensions, ExtensionContext},
 };
 use crate::Backend::Work::Work;
 use crate::Backend::Auth::User;
+use crate::Backend::Sessions::SessionStore;
+use crate::Backend::Users::UserRepository;
+use std::sync::Arc;
 
 pub struct Runtime {
     pub structure: ProjectStructure,
@@ -49,6 +53,18 @@ impl Runtime {
         }
     }
 
+    /// Use a custom session store for the backend.
+    pub fn with_session_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.backend = self.backend.with_session_store(store);
+        self
+    }
+
+    /// Use a custom user repository for the backend.
+    pub fn with_user_repository(mut self, repo: Arc<dyn UserRepository>) -> Self {
+        self.backend = self.backend.with_user_repository(repo);
+        self
+    }
+
     /// Load identity metadata.
     pub fn load_identity(&mut self) -> Result<(), String> {
         let id = Identity::load(&self.loader)?;
@@ -56,6 +72,18 @@ impl Runtime {
         Ok(())
     }
 
+    /// Load Describe/roles.yaml and make it the active role hierarchy.
+    pub fn load_roles(&mut self) -> Result<(), String> {
+        let hierarchy = self.loader.load_roles()?;
+        crate::Backend::Roles::set_role_hierarchy(hierarchy);
+        Ok(())
+    }
+
+    /// Load Describe/policies.yaml for use with `Middleware::with_policies`.
+    pub fn load_policies(&self) -> Result<PolicySet, String> {
+        self.loader.load_policies()
+    }
+
     /// Validate the project structure.
     pub fn validate(&self) -> Result<(), String> {
         self.validator.validate_folders()?;
@@ -65,6 +93,8 @@ impl Runtime {
     }
 
     /// Run middleware for an operation.
+    ///
+    /// Use `run_middleware_with` to pass an API key, action or resource.
     pub fn run_middleware(
         &self,
         middleware: &mut Middleware,
@@ -73,14 +103,21 @@ impl Runtime {
         input: Option<&str>,
         client_id: &str,
     ) -> Result<(), String> {
-        let ctx = MiddlewareContext {
-            session_token: session,
-            user,
-            input,
-            client_id,
-        };
+        let mut ctx = MiddlewareContext::new(client_id);
+        ctx.session_token = session;
+        ctx.user = user;
+        ctx.input = input;
+
+        self.run_middleware_with(middleware, &ctx)
+    }
 
-        middleware.run(&ctx)
+    /// Run middleware with a full context (see `MiddlewareContext::new`).
+    pub fn run_middleware_with(
+        &self,
+        middleware: &mut Middleware,
+        ctx: &MiddlewareContext,
+    ) -> Result<(), String> {
+        middleware.run(ctx)
     }
 
     /// Run an extension.
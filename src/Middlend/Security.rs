@@ -6,8 +6,10 @@
 //! - Input validation
 //! - Sanitization
 //! - Rate limiting
-//! - Session enforcement
+//! - Login throttling + account lockout
+//! - Session + API key enforcement
 //! - Role enforcement
+//! - Permission enforcement
 //! - Path safety checks
 //! - Basic intrusion detection
 //!
@@ -15,6 +17,7 @@
 
 use crate::Backend::Auth;
 use crate::Backend::Auth::User;
+use crate::Backend::ApiKeys::ApiKey;
 use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant};
 
@@ -55,19 +58,33 @@ pub fn validate_input(input: &str) -> Result<String, String> {
 }
 
 /// ------------------------------
-/// SESSION + ROLE ENFORCEMENT
+/// SESSION + ROLE + PERMISSION ENFORCEMENT
 /// ------------------------------
 
-/// Ensure a session token is valid.
+/// Ensure a session token is valid and not expired.
+///
+/// Each successful check slides the session's idle timer.
 pub fn enforce_session(token: &str) -> Result<(), String> {
-    if Auth::validate_session(token) {
+    Auth::touch_session(token).map(|_| ())
+}
+
+/// Ensure an API key is valid, unexpired and owned by a service account.
+///
+/// Returns the owning service account along with the key.
+pub fn enforce_api_key(key: &str) -> Result<(User, ApiKey), String> {
+    Auth::authenticate_api_key(key)
+}
+
+/// Ensure an API key's scopes cover the required scope.
+pub fn enforce_scope(key: &ApiKey, scope: &str) -> Result<(), String> {
+    if key.has_scope(scope) {
         Ok(())
     } else {
-        Err("Invalid or expired session token".into())
+        Err(format!("API key lacks scope '{}'", scope))
     }
 }
 
-/// Ensure a user has the required role.
+/// Ensure a user has the required role (inherited roles count).
 pub fn enforce_role(user: &User, required: &str) -> Result<(), String> {
     if Auth::user_has_role(user, required) {
         Ok(())
@@ -76,6 +93,15 @@ pub fn enforce_role(user: &User, required: &str) -> Result<(), String> {
     }
 }
 
+/// Ensure a user's roles grant the required permission.
+pub fn enforce_permission(user: &User, permission: &str) -> Result<(), String> {
+    if Auth::user_can(user, permission) {
+        Ok(())
+    } else {
+        Err(format!("User lacks permission '{}'", permission))
+    }
+}
+
 /// ------------------------------
 /// PATH SAFETY
 /// ------------------------------
@@ -109,27 +135,188 @@ impl RateLimiter {
         }
     }
 
-    /// Check if a client is allowed to proceed.
-    pub fn allow(&mut self, client_id: &str) -> bool {
-        let now = Instant::now();
+    /// Drop timestamps that fell out of the window and return the entry.
+    fn prune(&mut self, client_id: &str, now: Instant) -> &mut VecDeque<Instant> {
+        let window = self.window;
         let entry = self.requests.entry(client_id.into()).or_default();
 
         // Remove old timestamps
         while let Some(&front) = entry.front() {
-            if now.duration_since(front) > self.window {
+            if now.duration_since(front) > window {
                 entry.pop_front();
             } else {
                 break;
             }
         }
 
-        if entry.len() < self.max_requests {
+        entry
+    }
+
+    /// Check if a client is allowed to proceed.
+    pub fn allow(&mut self, client_id: &str) -> bool {
+        let now = Instant::now();
+        let max = self.max_requests;
+        let entry = self.prune(client_id, now);
+
+        if entry.len() < max {
             entry.push_back(now);
             true
         } else {
             false
         }
     }
+
+    /// Number of requests a client made inside the current window.
+    pub fn count(&mut self, client_id: &str) -> usize {
+        self.prune(client_id, Instant::now()).len()
+    }
+
+    /// Forget every recorded request of a client.
+    pub fn reset(&mut self, client_id: &str) {
+        self.requests.remove(client_id);
+    }
+
+    /// Drop clients whose requests all fell out of the window.
+    pub fn prune_idle(&mut self) {
+        let now = Instant::now();
+        let window = self.window;
+        self.requests.retain(|_, entry| {
+            entry
+                .back()
+                .is_some_and(|&last| now.duration_since(last) <= window)
+        });
+    }
+}
+
+/// ------------------------------
+/// LOGIN THROTTLING + LOCKOUT
+/// ------------------------------
+
+/// Active lockout for a username or client.
+#[derive(Clone, Debug)]
+pub struct Lockout {
+    pub until: Instant,
+    /// How many lockouts in a row (drives the exponential backoff).
+    pub strikes: u32,
+}
+
+/// Failed-login tracker with exponential lockout.
+///
+/// Failures are counted in a sliding window (via `RateLimiter`).
+/// Reaching `max_failures` locks the key for `base_lockout`, doubling
+/// on every repeated lockout up to `max_lockout`. Strikes are forgotten
+/// once a lockout has been over for `max_lockout`.
+pub struct LoginThrottle {
+    pub failures: RateLimiter,
+    pub base_lockout: Duration,
+    pub max_lockout: Duration,
+    pub lockouts: HashMap<String, Lockout>,
+}
+
+impl LoginThrottle {
+    pub fn new(
+        max_failures: usize,
+        window_seconds: u64,
+        base_lockout_seconds: u64,
+        max_lockout_seconds: u64,
+    ) -> Self {
+        Self {
+            failures: RateLimiter::new(max_failures, window_seconds),
+            base_lockout: Duration::from_secs(base_lockout_seconds),
+            max_lockout: Duration::from_secs(max_lockout_seconds),
+            lockouts: HashMap::new(),
+        }
+    }
+
+    /// Check whether a key may attempt a login.
+    ///
+    /// Returns the remaining lockout time if it is locked.
+    pub fn check(&mut self, key: &str) -> Result<(), Duration> {
+        self.prune();
+
+        match self.lockouts.get(key) {
+            Some(lock) => {
+                let now = Instant::now();
+                if lock.until > now {
+                    Err(lock.until - now)
+                } else {
+                    Ok(())
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Record a failed attempt. Returns the lockout duration if this
+    /// failure triggered a lockout.
+    pub fn record_failure(&mut self, key: &str) -> Option<Duration> {
+        self.prune();
+        self.failures.allow(key);
+
+        if self.failures.count(key) < self.failures.max_requests {
+            return None;
+        }
+
+        self.failures.reset(key);
+
+        let strikes = self.lockouts.get(key).map(|l| l.strikes).unwrap_or(0);
+        let factor = 2u32.saturating_pow(strikes);
+        let duration = self.base_lockout.saturating_mul(factor).min(self.max_lockout);
+
+        self.lockouts.insert(
+            key.into(),
+            Lockout {
+                until: Instant::now() + duration,
+                strikes: strikes.saturating_add(1),
+            },
+        );
+
+        log::warn!("Login lockout for '{}' ({}s)", key, duration.as_secs());
+        Some(duration)
+    }
+
+    /// Drop stale failure windows and lockouts whose strikes have expired.
+    fn prune(&mut self) {
+        self.failures.prune_idle();
+
+        let now = Instant::now();
+        let memory = self.max_lockout;
+        self.lockouts
+            .retain(|_, lock| lock.until.checked_add(memory).is_none_or(|end| end > now));
+    }
+
+    /// Record a successful login, clearing failures and strikes.
+    pub fn record_success(&mut self, key: &str) {
+        self.failures.reset(key);
+        self.lockouts.remove(key);
+    }
+
+    /// Lift a lockout (admin action). Returns `false` if none was active.
+    pub fn unlock(&mut self, key: &str) -> bool {
+        self.failures.reset(key);
+        let now = Instant::now();
+        self.lockouts
+            .remove(key)
+            .map(|l| l.until > now)
+            .unwrap_or(false)
+    }
+
+    /// List active lockouts with their remaining time.
+    pub fn active_lockouts(&self) -> Vec<(String, Duration)> {
+        let now = Instant::now();
+        self.lockouts
+            .iter()
+            .filter(|(_, l)| l.until > now)
+            .map(|(k, l)| (k.clone(), l.until - now))
+            .collect()
+    }
+}
+
+impl Default for LoginThrottle {
+    /// 5 failures in 15 minutes → 1 minute lockout, doubling up to 1 hour.
+    fn default() -> Self {
+        Self::new(5, 15 * 60, 60, 60 * 60)
+    }
 }
 
 /// ------------------------------
@@ -153,6 +340,7 @@ pub fn detect_intrusion(route: &str, payload: &str) -> bool {
 pub struct SecurityPolicy {
     pub require_session: bool,
     pub require_role: Option<String>,
+    pub require_permission: Option<String>,
     pub sanitize_inputs: bool,
     pub rate_limit: Option<(usize, u64)>, // (max_requests, window_seconds)
 }
@@ -162,6 +350,7 @@ impl SecurityPolicy {
         Self {
             require_session: true,
             require_role: None,
+            require_permission: None,
             sanitize_inputs: true,
             rate_limit: None,
         }